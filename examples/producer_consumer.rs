@@ -14,12 +14,12 @@ fn main() {
             let mut count = 10;
             while count != 0 {
                 yield_thread();
-                let mut queue = mtx.lock();
+                let mut queue = mtx.lock().unwrap();
                 count -= 1;
                 queue.push(count);
             }
 
-            let lock = mtx.lock();
+            let lock = mtx.lock().unwrap();
 
             println!("Producer is done!");
 
@@ -30,7 +30,7 @@ fn main() {
 
         let consumer = Fiber::new(move || loop {
             yield_thread();
-            let mut queue = m.lock();
+            let mut queue = m.lock().unwrap();
             cv.wait_pred(&m.mutex, || {
                 println!("Consumer: Waiting...");
                 return *stopped.get() || !queue.is_empty();
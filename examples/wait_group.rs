@@ -0,0 +1,18 @@
+use greenie::{common::WaitGroup, greeny_main, Fiber};
+#[greeny_main]
+fn main() {
+    let wg = WaitGroup::new();
+    for i in 0..10 {
+        wg.add(1);
+        let fiber = Fiber::new(move || {
+            println!("Fiber #{} started", i);
+            greenie::yield_thread();
+            println!("Fiber #{} done", i);
+            wg.done();
+        });
+        fiber.start().unwrap();
+    }
+
+    wg.wait();
+    println!("All fibers done");
+}
@@ -1,3 +1,4 @@
+pub mod priority;
 pub mod round_robin;
 pub mod shared_work;
 #[cfg(feature = "atomics")]
@@ -11,7 +12,17 @@ pub trait Algorithm {
     }
     fn awakened(&mut self, _: Ptr<Context>);
     fn pick_next(&mut self) -> Ptr<Context>;
+    /// Number of contexts currently waiting in the ready queue, used by `runtime_metrics`.
+    ///
+    /// The default returns 0, so `RuntimeMetrics::ready_queue_len` stays 0 for algorithms that don't override it.
+    /// Every algorithm in this crate does.
+    fn ready_len(&self) -> usize {
+        0
+    }
     fn notify(&mut self) {}
+    /// Called when priority of `ctx` changes, e.g. when it inherits priority of a fiber waiting on a mutex it holds.
+    /// `ctx` may be in the ready queue.
+    fn priority_changed(&mut self, _: Ptr<Context>) {}
     fn steal(&mut self) -> Ptr<Context> {
         Ptr::null()
     }
@@ -0,0 +1,84 @@
+use crate::ctx::*;
+use crate::ptr::*;
+
+use intrusive_collections::LinkedList;
+
+/// Runs the ready context with the highest `Context::priority` first, contexts of equal priority run in FIFO order.
+///
+/// Priority of a fiber holding a `Mutex` is raised to the priority of fibers waiting on it, so a low priority owner is
+/// not starved by medium priority fibers while a high priority one waits.
+///
+/// The dispatcher always runs first, so timers and remote wakeups are handled even while high priority fibers keep
+/// yielding to each other.
+///
+/// # Example
+/// ```rust
+/// use greenie::algorithm::priority::Priority;
+/// use greenie::common::Mutex;
+/// use greenie::ctx::Context;
+/// use greenie::*;
+/// create_main(|| {
+///     scheduler::RUNTIME.with(|rt| rt.get().set_algorithm(Box::new(Priority::new())));
+///     let m = Mutex::new(());
+///     let g = m.lock().unwrap();
+///     let h = spawn_greenie(|m: Mutex<()>| drop(m.lock().unwrap()), (m.clone(),));
+///     h.set_priority(10);
+///     yield_thread();
+///     assert_eq!(Context::active().priority(), 10);
+///     drop(g);
+///     assert_eq!(Context::active().priority(), 0);
+///     h.join().unwrap();
+/// });
+/// ```
+pub struct Priority {
+    rqueue: LinkedList<ReadyAdapter>,
+}
+
+impl Priority {
+    pub fn new() -> Self {
+        Self {
+            rqueue: LinkedList::new(ReadyAdapter::new()),
+        }
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+use super::*;
+
+impl Algorithm for Priority {
+    fn awakened(&mut self, context: Ptr<Context>) {
+        if context.is_dispatcher {
+            self.rqueue.push_front(context);
+            return;
+        }
+        let priority = context.priority();
+        let mut cursor = self.rqueue.front_mut();
+        while let Some(ctx) = cursor.get() {
+            if !ctx.is_dispatcher && ctx.priority() < priority {
+                break;
+            }
+            cursor.move_next();
+        }
+        cursor.insert_before(context);
+    }
+
+    fn pick_next(&mut self) -> Ptr<Context> {
+        self.rqueue.pop_front().unwrap_or_else(Ptr::null)
+    }
+
+    fn ready_len(&self) -> usize {
+        self.rqueue.iter().count()
+    }
+
+    fn priority_changed(&mut self, context: Ptr<Context>) {
+        if context.ready_hook.is_linked() {
+            unsafe { self.rqueue.cursor_mut_from_ptr(context.0).remove() };
+            self.awakened(context);
+        }
+    }
+}
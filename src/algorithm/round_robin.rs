@@ -38,6 +38,10 @@ impl Algorithm for RoundRobin {
         ctx
     }
 
+    fn ready_len(&self) -> usize {
+        self.rqueue.iter().count()
+    }
+
     fn notify(&mut self) {
         if self.suspsend {
             let lk = self.mtx.lock();
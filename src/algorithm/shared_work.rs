@@ -52,6 +52,10 @@ impl Algorithm for SharedWork {
         ctx
     }
 
+    fn ready_len(&self) -> usize {
+        self.lqueue.iter().count() + self.rqueue.lock().iter().count()
+    }
+
     fn notify(&mut self) {
         if self.suspsend {
             let lk = self.mtx.lock();
@@ -80,4 +80,8 @@ impl Algorithm for WorkStealing {
 
         victim
     }
+
+    fn ready_len(&self) -> usize {
+        self.rqueue.len()
+    }
 }
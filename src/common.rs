@@ -1,9 +1,46 @@
 pub mod barrier;
+pub mod broadcast;
 pub mod channel;
 pub mod condvar;
+pub mod event;
 pub mod mutex;
+pub mod once;
+pub mod oneshot;
+pub mod reentrant;
+pub mod rwlock;
+pub mod select;
+pub mod semaphore;
+pub mod waitgroup;
+pub mod watch;
 
-pub use channel::Channel;
+pub use channel::{channel, unbounded, Channel, ChannelStatus, Receiver, SendError, Sender};
+pub use condvar::CvStatus;
+pub use event::{Event, Notify};
+pub use mutex::Fairness;
+pub use once::{Lazy, Once, OnceCell};
+pub use oneshot::{oneshot, Canceled, OneshotReceiver, OneshotSender};
+pub use reentrant::{ReentrantMutex, ReentrantMutexGuard};
+pub use select::Select;
+pub use semaphore::{Semaphore, SemaphorePermit};
+pub use waitgroup::{WaitGroup, WaitGuard};
+
+pub use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+
+/// Wrap `guard` into `PoisonError` if the lock is poisoned.
+fn poison_result<G>(poison: crate::ptr::Ptr<bool>, guard: G) -> LockResult<G> {
+    if *poison {
+        Err(PoisonError::new(guard))
+    } else {
+        Ok(guard)
+    }
+}
+
+fn try_poison_result<G>(poison: crate::ptr::Ptr<bool>, guard: Option<G>) -> TryLockResult<G> {
+    match guard {
+        Some(guard) => poison_result(poison, guard).map_err(TryLockError::Poisoned),
+        None => Err(TryLockError::WouldBlock),
+    }
+}
 
 ///A mutual exclusion primitive useful for protecting shared data
 ///
@@ -11,8 +48,12 @@ pub use channel::Channel;
 /// new constructor. Each mutex has a type parameter which represents the data that it is protecting. The data can only be accessed
 /// through the RAII guards returned from lock and try_lock, which guarantees that the data is only ever accessed when the mutex
 /// is locked.
+///
+/// The mutex is poisoned if a fiber panics while holding it, later `lock` calls return `PoisonError` that still gives access to
+/// the data, like `std::sync::Mutex`.
 pub struct Mutex<T> {
     value: crate::ptr::Ptr<T>,
+    poison: crate::ptr::Ptr<bool>,
     pub mutex: mutex::Mutex,
 }
 
@@ -20,6 +61,7 @@ impl<T> Clone for Mutex<T> {
     fn clone(&self) -> Self {
         Self {
             value: self.value,
+            poison: self.poison,
             mutex: self.mutex.clone(),
         }
     }
@@ -27,38 +69,80 @@ impl<T> Clone for Mutex<T> {
 
 impl<T> Mutex<T> {
     /// Creates a new mutex in an unlocked state ready for use.
+    #[track_caller]
     pub fn new(value: T) -> Self {
         Self {
             value: crate::ptr::Ptr::new(value),
+            poison: crate::ptr::Ptr::new(false),
             mutex: mutex::Mutex::new(),
         }
     }
+    /// Creates a new mutex in an unlocked state that passes ownership to waiters according to `fairness`.
+    #[track_caller]
+    pub fn with_fairness(value: T, fairness: Fairness) -> Self {
+        Self {
+            value: crate::ptr::Ptr::new(value),
+            poison: crate::ptr::Ptr::new(false),
+            mutex: mutex::Mutex::with_fairness(fairness),
+        }
+    }
     /// Acquires a mutex, blocking the current thread until it is able to do so.
     ///
     /// This function will block the local thread until it is available to acquire the mutex. Upon returning, the thread is the only thread with
     /// the lock held. An RAII guard is returned to allow scoped unlock of the lock. When the guard goes out of scope, the mutex will be
     /// unlocked.
     ///
+    /// Returns `PoisonError` with the guard inside if the mutex is poisoned.
+    ///
     /// ## Panics
     /// Panics if deadlock found
-    pub fn lock(&self) -> MutexGuard<'_, T> {
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
         self.mutex.lock();
-        MutexGuard {
-            mtx: self.mutex.clone(),
-            value: self.value.get(),
-        }
+        poison_result(self.poison, self.guard())
     }
     /// Attempts to acquire this lock.
-    /// If the lock could not be acquired at this time, then `None` is returned. Otherwise, RAII guard returned is returned.
-    /// This function does not block.
-    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
-        if self.mutex.try_lock() {
-            Some(MutexGuard {
-                mtx: self.mutex.clone(),
-                value: self.value.get(),
-            })
+    /// If the lock could not be acquired at this time, then `TryLockError::WouldBlock` is returned. Otherwise, RAII guard
+    /// is returned. This function does not block.
+    pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+        let guard = if self.mutex.try_lock() {
+            Some(self.guard())
+        } else {
+            None
+        };
+        try_poison_result(self.poison, guard)
+    }
+    /// Attempts to acquire this lock, blocking the current fiber for roughly no longer than `dur`.
+    ///
+    /// Returns `TryLockError::WouldBlock` if the lock could not be acquired before the timeout.
+    pub fn try_lock_for(&self, dur: std::time::Duration) -> TryLockResult<MutexGuard<'_, T>> {
+        self.try_lock_until(std::time::Instant::now() + dur)
+    }
+    /// Attempts to acquire this lock, blocking the current fiber until `deadline` passes.
+    ///
+    /// Returns `TryLockError::WouldBlock` if the lock could not be acquired before the deadline.
+    pub fn try_lock_until(&self, deadline: std::time::Instant) -> TryLockResult<MutexGuard<'_, T>> {
+        let guard = if self.mutex.try_lock_until(deadline) {
+            Some(self.guard())
         } else {
             None
+        };
+        try_poison_result(self.poison, guard)
+    }
+    /// A fiber panicked while holding this mutex.
+    pub fn is_poisoned(&self) -> bool {
+        *self.poison
+    }
+    /// Clears the poisoned state, the data is considered consistent again.
+    pub fn clear_poison(&self) {
+        *self.poison.get() = false;
+    }
+
+    fn guard(&self) -> MutexGuard<'_, T> {
+        MutexGuard {
+            mtx: self.mutex.clone(),
+            value: self.value.get(),
+            poison: self.poison,
+            panicking: crate::ctx::Context::panicking(),
         }
     }
 }
@@ -66,10 +150,16 @@ impl<T> Mutex<T> {
 pub struct MutexGuard<'a, T> {
     value: &'a mut T,
     pub(crate) mtx: mutex::Mutex,
+    poison: crate::ptr::Ptr<bool>,
+    /// Guard was created while unwinding, dropping it then does not poison the mutex.
+    panicking: bool,
 }
 
 impl<T> Drop for MutexGuard<'_, T> {
     fn drop(&mut self) {
+        if !self.panicking && crate::ctx::Context::panicking() {
+            *self.poison.get() = true;
+        }
         self.mtx.unlock();
     }
 }
@@ -92,6 +182,12 @@ impl<T> DerefMut for MutexGuard<'_, T> {
 /// until another thread both modifies a shared variable (the condition), and notifies the condition variable.
 pub struct Condvar(condvar::Condvar);
 
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Condvar {
     #[inline(always)]
     pub fn new() -> Self {
@@ -117,6 +213,36 @@ impl Condvar {
     pub fn wait_pred<'a, T>(&self, x: &MutexGuard<'a, T>, pred: impl FnMut() -> bool) {
         self.0.wait_pred(&x.mtx, pred);
     }
+    /// Waits on this condition variable for a notification, timing out after a specified duration.
+    ///
+    /// Returns `CvStatus::Timeout` if the wait timed out.
+    #[inline(always)]
+    pub fn wait_timeout<'a, T>(&self, x: &MutexGuard<'a, T>, dur: std::time::Duration) -> CvStatus {
+        self.0.wait_timeout(&x.mtx, dur)
+    }
+    /// Waits on this condition variable for a notification until `deadline` passes.
+    ///
+    /// Returns `CvStatus::Timeout` if the deadline passed before the fiber was notified.
+    #[inline(always)]
+    pub fn wait_until<'a, T>(
+        &self,
+        x: &MutexGuard<'a, T>,
+        deadline: std::time::Instant,
+    ) -> CvStatus {
+        self.0.wait_until(&x.mtx, deadline)
+    }
+    /// Waits on this condition variable until `pred` returns true, timing out after a specified duration.
+    ///
+    /// Returns `CvStatus::Timeout` if `pred` is still false after `dur` elapsed.
+    #[inline(always)]
+    pub fn wait_timeout_pred<'a, T>(
+        &self,
+        x: &MutexGuard<'a, T>,
+        dur: std::time::Duration,
+        pred: impl FnMut() -> bool,
+    ) -> CvStatus {
+        self.0.wait_timeout_pred(&x.mtx, dur, pred)
+    }
     /// If any threads are waiting on this condvar, calling notify_one unblocks one of the waiting threads.
     #[inline(always)]
     pub fn notify_one(&self) {
@@ -128,3 +254,172 @@ impl Condvar {
         self.0.notify_all()
     }
 }
+
+/// A reader-writer lock
+///
+/// This type of lock allows a number of readers or at most one writer at any point in time. The write portion of this lock typically
+/// allows modification of the underlying data (exclusive access) and the read portion of this lock typically allows for read-only
+/// access (shared access).
+///
+/// Fibers waiting for the lock are parked and do not block the OS thread. Writers are preferred over readers so a steady stream
+/// of readers can't starve a writer.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::RwLock;
+/// use greenie::*;
+/// create_main(|| {
+///     let lock = RwLock::new(5);
+///     {
+///         let r1 = lock.read().unwrap();
+///         let r2 = lock.read().unwrap();
+///         assert_eq!(*r1 + *r2, 10);
+///     }
+///     *lock.write().unwrap() += 1;
+///     assert_eq!(*lock.read().unwrap(), 6);
+/// });
+/// ```
+pub struct RwLock<T> {
+    value: crate::ptr::Ptr<T>,
+    poison: crate::ptr::Ptr<bool>,
+    pub lock: rwlock::RwLock,
+}
+
+impl<T> Clone for RwLock<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            poison: self.poison,
+            lock: self.lock,
+        }
+    }
+}
+
+impl<T> RwLock<T> {
+    /// Creates a new instance of an `RwLock<T>` which is unlocked.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: crate::ptr::Ptr::new(value),
+            poison: crate::ptr::Ptr::new(false),
+            lock: rwlock::RwLock::new(),
+        }
+    }
+    /// Locks this rwlock with shared read access, blocking the current fiber until it can be acquired.
+    ///
+    /// The calling fiber will be blocked until there are no more writers which hold or wait for the lock. There may be other
+    /// readers currently inside the lock when this method returns. Returns `PoisonError` with the guard inside if the lock is
+    /// poisoned.
+    ///
+    /// ## Panics
+    /// Panics if the lock is already held for writing by the current fiber
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        self.lock.read();
+        poison_result(self.poison, self.read_guard())
+    }
+    /// Locks this rwlock with exclusive write access, blocking the current fiber until it can be acquired.
+    ///
+    /// This function will not return while other writers or other readers currently have access to the lock. Returns
+    /// `PoisonError` with the guard inside if the lock is poisoned.
+    ///
+    /// ## Panics
+    /// Panics if the lock is already held for writing by the current fiber
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.lock.write();
+        poison_result(self.poison, self.write_guard())
+    }
+    /// Attempts to acquire this rwlock with shared read access.
+    ///
+    /// If the access could not be granted at this time, then `TryLockError::WouldBlock` is returned. This function does not
+    /// block.
+    pub fn try_read(&self) -> TryLockResult<RwLockReadGuard<'_, T>> {
+        let guard = if self.lock.try_read() {
+            Some(self.read_guard())
+        } else {
+            None
+        };
+        try_poison_result(self.poison, guard)
+    }
+    /// Attempts to acquire this rwlock with exclusive write access.
+    ///
+    /// If the access could not be granted at this time, then `TryLockError::WouldBlock` is returned. This function does not
+    /// block.
+    pub fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
+        let guard = if self.lock.try_write() {
+            Some(self.write_guard())
+        } else {
+            None
+        };
+        try_poison_result(self.poison, guard)
+    }
+    /// A fiber panicked while holding write access to this rwlock.
+    pub fn is_poisoned(&self) -> bool {
+        *self.poison
+    }
+    /// Clears the poisoned state, the data is considered consistent again.
+    pub fn clear_poison(&self) {
+        *self.poison.get() = false;
+    }
+
+    fn read_guard(&self) -> RwLockReadGuard<'_, T> {
+        RwLockReadGuard {
+            value: self.value.get(),
+            lock: self.lock,
+        }
+    }
+
+    fn write_guard(&self) -> RwLockWriteGuard<'_, T> {
+        RwLockWriteGuard {
+            value: self.value.get(),
+            lock: self.lock,
+            poison: self.poison,
+            panicking: crate::ctx::Context::panicking(),
+        }
+    }
+}
+
+pub struct RwLockReadGuard<'a, T> {
+    value: &'a T,
+    lock: rwlock::RwLock,
+}
+
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock_read();
+    }
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+    value: &'a mut T,
+    lock: rwlock::RwLock,
+    poison: crate::ptr::Ptr<bool>,
+    panicking: bool,
+}
+
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.panicking && crate::ctx::Context::panicking() {
+            *self.poison.get() = true;
+        }
+        self.lock.unlock_write();
+    }
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
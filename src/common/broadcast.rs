@@ -0,0 +1,229 @@
+//! Multi-producer, multi-consumer broadcast channel.
+//!
+//! Every value sent is delivered to each receiver that was subscribed at the moment of sending. Values are kept in a
+//! bounded ring buffer and each receiver has its own cursor in it. Sending never blocks: once the buffer is full the
+//! oldest value is overwritten, and a receiver that did not read it yet gets `RecvError::Lagged` with the number of
+//! values it missed, then continues from the oldest value still in the buffer.
+//!
+//! # Example
+//! ```rust
+//! use greenie::common::broadcast;
+//! use greenie::*;
+//! create_main(|| {
+//!     let (tx, mut rx1) = broadcast::channel(2);
+//!     let mut rx2 = tx.subscribe();
+//!     tx.send(1).unwrap();
+//!     tx.send(2).unwrap();
+//!     tx.send(3).unwrap();
+//!     assert_eq!(rx1.recv(), Err(broadcast::RecvError::Lagged(1)));
+//!     assert_eq!(rx1.recv(), Ok(2));
+//!     drop(tx);
+//!     assert_eq!(rx2.try_recv(), Err(broadcast::RecvError::Lagged(1)));
+//!     assert_eq!(rx1.recv(), Ok(3));
+//!     assert_eq!(rx1.recv(), Err(broadcast::RecvError::Closed));
+//! });
+//! ```
+
+use super::channel::{ChannelStatus, SendError};
+use crate::ctx::*;
+use crate::detail::spinlock::SpinLock;
+use crate::ptr::*;
+use std::collections::LinkedList;
+
+struct Shared<T> {
+    buffer: Vec<Option<T>>,
+    /// Position of the next value, value at position `pos` lives in `buffer[pos % capacity]`.
+    tail: u64,
+    waiting: LinkedList<Ptr<Context>>,
+    senders: usize,
+    receivers: usize,
+    wait_queue_splk: SpinLock,
+}
+
+impl<T> Shared<T> {
+    fn capacity(&self) -> u64 {
+        self.buffer.len() as u64
+    }
+
+    fn release(shared: Ptr<Self>) {
+        if shared.senders == 0 && shared.receivers == 0 {
+            let _ = unsafe { Box::from_raw(shared.0) };
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// No values to receive right now.
+    Empty,
+    /// Receiver fell behind and the given number of values was overwritten before it could read them.
+    Lagged(u64),
+    /// All senders were dropped and no values are left.
+    Closed,
+}
+
+/// Sending half of a broadcast channel.
+pub struct Sender<T> {
+    shared: Ptr<Shared<T>>,
+}
+
+/// Receiving half of a broadcast channel, each receiver gets every value sent after it subscribed.
+pub struct Receiver<T> {
+    shared: Ptr<Shared<T>>,
+    /// Position of the next value to receive.
+    next: u64,
+}
+
+/// Creates a broadcast channel that keeps up to `capacity` values for slow receivers.
+///
+/// ## Panics
+/// Panics if `capacity` is zero
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    if capacity == 0 {
+        panic!("greenie: broadcast channel capacity must be positive");
+    }
+    let shared = Ptr::new(Shared {
+        buffer: (0..capacity).map(|_| None).collect(),
+        tail: 0,
+        waiting: LinkedList::new(),
+        senders: 1,
+        receivers: 1,
+        wait_queue_splk: SpinLock::new(()),
+    });
+    (Sender { shared }, Receiver { shared, next: 0 })
+}
+
+impl<T: Clone> Sender<T> {
+    /// Sends a value to all receivers and returns their number, never blocks.
+    ///
+    /// Fails with `ChannelStatus::Disconnected` if there are no receivers, the value is returned back in this case.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let shared = self.shared.get();
+        let _lk = self.shared.wait_queue_splk.lock();
+        if shared.receivers == 0 {
+            return Err(SendError {
+                status: ChannelStatus::Disconnected,
+                value,
+            });
+        }
+        let slot = (shared.tail % shared.capacity()) as usize;
+        shared.buffer[slot] = Some(value);
+        shared.tail += 1;
+        while let Some(ctx) = shared.waiting.pop_front() {
+            Context::resume(ctx);
+        }
+        Ok(shared.receivers)
+    }
+    /// Creates a new receiver that gets values sent after this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let shared = self.shared.get();
+        let _lk = self.shared.wait_queue_splk.lock();
+        shared.receivers += 1;
+        Receiver {
+            shared: self.shared,
+            next: shared.tail,
+        }
+    }
+    /// Number of receivers subscribed to the channel.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.receivers
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next value, blocking the current fiber until it is sent.
+    ///
+    /// Returns `RecvError::Lagged` if this receiver missed values, the next call continues from the oldest value still
+    /// kept by the channel. Returns `RecvError::Closed` once all senders are dropped and every value was received.
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        loop {
+            let shared = self.shared;
+            let active_ctx = Context::active();
+            {
+                let _lk = shared.wait_queue_splk.lock();
+                match self.try_recv_locked() {
+                    Err(RecvError::Empty) => shared.get().waiting.push_back(active_ctx),
+                    result => return result,
+                }
+            }
+            active_ctx
+                .scheduler
+                .get()
+                .park(BlockedOn::ChannelRecv(shared.0 as usize));
+        }
+    }
+    /// Attempts to receive the next value without blocking.
+    pub fn try_recv(&mut self) -> Result<T, RecvError> {
+        let shared = self.shared;
+        let _lk = shared.wait_queue_splk.lock();
+        self.try_recv_locked()
+    }
+
+    fn try_recv_locked(&mut self) -> Result<T, RecvError> {
+        let shared = self.shared.get();
+        if self.next == shared.tail {
+            return if shared.senders == 0 {
+                Err(RecvError::Closed)
+            } else {
+                Err(RecvError::Empty)
+            };
+        }
+        let oldest = shared.tail.saturating_sub(shared.capacity());
+        if self.next < oldest {
+            let missed = oldest - self.next;
+            self.next = oldest;
+            return Err(RecvError::Lagged(missed));
+        }
+        let slot = (self.next % shared.capacity()) as usize;
+        self.next += 1;
+        Ok(shared.buffer[slot].clone().unwrap())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        let _lk = self.shared.wait_queue_splk.lock();
+        self.shared.get().senders += 1;
+        Self {
+            shared: self.shared,
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    /// New receiver starts at the same position as this one.
+    fn clone(&self) -> Self {
+        let _lk = self.shared.wait_queue_splk.lock();
+        self.shared.get().receivers += 1;
+        Self {
+            shared: self.shared,
+            next: self.next,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let shared = self.shared.get();
+        {
+            let _lk = self.shared.wait_queue_splk.lock();
+            shared.senders -= 1;
+            if shared.senders == 0 {
+                while let Some(ctx) = shared.waiting.pop_front() {
+                    Context::resume(ctx);
+                }
+            }
+        }
+        Shared::release(self.shared);
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        {
+            let _lk = self.shared.wait_queue_splk.lock();
+            self.shared.get().receivers -= 1;
+        }
+        Shared::release(self.shared);
+    }
+}
@@ -1,247 +1,277 @@
+use super::select::{Operation, SelectHandle};
 use crate::ctx::*;
 use crate::ptr::*;
 
-use std::collections::LinkedList;
+use std::collections::{LinkedList, VecDeque};
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
 
 struct ChannelInner<T> {
+    /// Maximum number of buffered values, zero for rendezvous channel.
     capacity: usize,
-    slots: Vec<Option<T>>,
+    /// Buffer grows instead of blocking producers.
+    unbounded: bool,
+    queue: VecDeque<T>,
     waiting_producers: LinkedList<Ptr<Context>>,
     waiting_consumers: LinkedList<Ptr<Context>>,
+    /// Fibers in `Select` waiting to receive, unlike `waiting_consumers` they may pick another operation.
+    selecting_consumers: LinkedList<Ptr<Context>>,
+    /// Producer of rendezvous channel whose value is not taken yet.
+    rendezvous_sender: Ptr<Context>,
     closed: bool,
-    cidx: usize,
-    pidx: usize,
+    /// Closed because all senders or all receivers were dropped.
+    disconnected: bool,
+    senders: usize,
+    receivers: usize,
 }
 
 impl<T> ChannelInner<T> {
+    fn new(capacity: usize, unbounded: bool) -> Self {
+        Self {
+            capacity,
+            unbounded,
+            queue: VecDeque::with_capacity(capacity),
+            waiting_producers: LinkedList::new(),
+            waiting_consumers: LinkedList::new(),
+            selecting_consumers: LinkedList::new(),
+            rendezvous_sender: Ptr::null(),
+            closed: false,
+            disconnected: false,
+            senders: 0,
+            receivers: 0,
+        }
+    }
+
+    fn is_rendezvous(&self) -> bool {
+        !self.unbounded && self.capacity == 0
+    }
+
+    /// Rendezvous channel holds the value being handed over in its single slot.
     fn is_full_(&self) -> bool {
-        self.cidx == ((self.pidx + 1) % self.capacity)
+        !self.unbounded && self.queue.len() >= self.capacity.max(1)
     }
 
     fn is_empty_(&self) -> bool {
-        self.cidx == self.pidx
+        self.queue.is_empty()
+    }
+
+    /// Value can be sent without blocking, rendezvous channel also needs a receiver parked in `recv`.
+    fn can_put(&self) -> bool {
+        if self.is_rendezvous() {
+            self.queue.is_empty() && !self.waiting_consumers.is_empty()
+        } else {
+            !self.is_full_()
+        }
     }
 
     pub fn is_closed(&self) -> bool {
         self.closed
     }
 
-    pub fn close(&mut self) {
-        //let active_ctx = Context::active();
+    /// Status returned by operations that fail because the channel is closed.
+    fn closed_status(&self) -> ChannelStatus {
+        if self.disconnected {
+            ChannelStatus::Disconnected
+        } else {
+            ChannelStatus::Closed
+        }
+    }
 
+    fn disconnect(&mut self) {
+        if !self.closed {
+            self.disconnected = true;
+            self.close();
+        }
+    }
+
+    pub fn close(&mut self) {
         if !self.closed {
             self.closed = true;
 
-            while let Some(producer_ctx) = self.waiting_producers.pop_front() {
-                let expected = self as *const ChannelInner<T>;
-                let result = producer_ctx.get().twstatus.compare_exchange(
-                    expected as *mut _,
-                    -1i8 as *mut i8,
-                    std::sync::atomic::Ordering::Acquire,
-                    std::sync::atomic::Ordering::Relaxed,
-                );
-                match result {
-                    Ok(_) => {
-                        break;
-                    }
-                    Err(x) => {
-                        if x.is_null() {
-                            break;
-                        }
-                    }
-                }
+            while !self.waiting_producers.is_empty() {
+                wake_one(&mut self.waiting_producers);
+            }
+            while !self.waiting_consumers.is_empty() {
+                wake_one(&mut self.waiting_consumers);
             }
+            while !self.selecting_consumers.is_empty() {
+                wake_one(&mut self.selecting_consumers);
+            }
+            let sender = self.rendezvous_sender;
+            if !sender.is_null() && sender.claim_wakeup() {
+                Context::resume(sender);
+            }
+        }
+    }
 
-            while let Some(consumer_ctx) = self.waiting_consumers.pop_front() {
-                let expected = self as *const ChannelInner<T>;
-                let result = consumer_ctx.get().twstatus.compare_exchange(
-                    expected as *mut _,
-                    -1i8 as *mut i8,
-                    std::sync::atomic::Ordering::Acquire,
-                    std::sync::atomic::Ordering::Relaxed,
-                );
-                match result {
-                    Ok(_) => {
-                        Context::resume(consumer_ctx);
-
-                        break;
-                    }
-                    Err(x) => {
-                        if x.is_null() {
-                            Context::resume(consumer_ctx);
-
-                            break;
-                        }
-                    }
+    /// Suspend active context until it is woken up by the other side of the channel or `deadline` passes.
+    ///
+    /// Returns `true` if the deadline passed.
+    fn park(&mut self, what: BlockedOn, deadline: Option<Instant>) -> bool {
+        let active_ctx = Context::active();
+        active_ctx.twstatus.store(
+            self as *const Self as *mut i8,
+            std::sync::atomic::Ordering::Release,
+        );
+        let scheduler = active_ctx.scheduler.get();
+        match deadline {
+            Some(deadline) => {
+                if scheduler.park_until(what, deadline) {
+                    let queue = match what {
+                        BlockedOn::ChannelSend(_) => &mut self.waiting_producers,
+                        _ => &mut self.waiting_consumers,
+                    };
+                    unlink_waiter(queue, active_ctx);
+                    return true;
                 }
+                false
+            }
+            None => {
+                scheduler.park(what);
+                false
             }
         }
     }
 
-    pub fn push(&mut self, value: T) -> ChannelStatus {
+    pub fn push(&mut self, value: T) -> Result<(), SendError<T>> {
+        self.push_until(value, None)
+    }
+
+    pub fn push_until(&mut self, value: T, deadline: Option<Instant>) -> Result<(), SendError<T>> {
         let active_ctx = Context::active();
         loop {
             if self.is_closed() {
-                return ChannelStatus::Closed;
+                return Err(SendError::new(self.closed_status(), value));
             } else if self.is_full_() {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(SendError::new(ChannelStatus::Timeout, value));
+                }
                 // Full? Suspend until receiver will receive value from current channel.
                 self.waiting_producers.push_back(active_ctx);
-                active_ctx
-                    .twstatus
-                    .store(0 as *mut _, std::sync::atomic::Ordering::Release);
-                active_ctx.scheduler.get().suspend_thread(active_ctx);
+                let what = BlockedOn::ChannelSend(self as *const Self as usize);
+                if self.park(what, deadline) {
+                    return Err(SendError::new(ChannelStatus::Timeout, value));
+                }
             } else {
-                self.slots[self.pidx] = Some(value);
-                self.pidx = (self.pidx + 1) % self.capacity;
-
-                while let Some(consumer_ctx) = self.waiting_consumers.pop_front() {
-                    let expected = self as *const ChannelInner<T>;
-                    let result = consumer_ctx.get().twstatus.compare_exchange(
-                        expected as *mut _,
-                        -1i8 as *mut i8,
-                        std::sync::atomic::Ordering::Acquire,
-                        std::sync::atomic::Ordering::Relaxed,
-                    );
-                    match result {
-                        Ok(_) => {
-                            Context::resume(consumer_ctx);
-
-                            break;
-                        }
-                        Err(x) => {
-                            if x.is_null() {
-                                Context::resume(consumer_ctx);
-                                break;
-                            }
-                        }
-                    }
+                self.put(value);
+                if self.is_rendezvous() {
+                    return self.wait_taken(deadline);
                 }
-
-                return ChannelStatus::Success;
+                return Ok(());
             }
         }
     }
 
-    pub fn try_push(&mut self, value: T) -> ChannelStatus {
-        loop {
-            if self.is_closed() {
-                return ChannelStatus::Closed;
-            } else if self.is_full_() {
-                return ChannelStatus::Full;
-            } else {
-                self.slots[self.pidx] = Some(value);
-                self.pidx = (self.pidx + 1) % self.capacity;
-
-                while let Some(consumer_ctx) = self.waiting_consumers.pop_front() {
-                    let expected = self as *const ChannelInner<T>;
-                    let result = consumer_ctx.get().twstatus.compare_exchange(
-                        expected as *mut _,
-                        -1i8 as *mut i8,
-                        std::sync::atomic::Ordering::Acquire,
-                        std::sync::atomic::Ordering::Relaxed,
-                    );
-                    match result {
-                        Ok(_) => {
-                            Context::resume(consumer_ctx);
-                            break;
-                        }
-                        Err(x) => {
-                            if x.is_null() {
-                                Context::resume(consumer_ctx);
-
-                                break;
-                            }
-                        }
-                    }
-                }
+    /// Wait until receiver takes the value just put to rendezvous channel, take it back if the channel gets closed or
+    /// `deadline` passes.
+    fn wait_taken(&mut self, deadline: Option<Instant>) -> Result<(), SendError<T>> {
+        let active_ctx = Context::active();
+        self.rendezvous_sender = active_ctx;
+        self.park(
+            BlockedOn::ChannelSend(self as *const Self as usize),
+            deadline,
+        );
+        if self.rendezvous_sender != active_ctx {
+            return Ok(());
+        }
+        self.rendezvous_sender = Ptr::null();
+        let value = self.queue.pop_back().unwrap();
+        wake_one(&mut self.waiting_producers);
+        let status = if self.is_closed() {
+            self.closed_status()
+        } else {
+            ChannelStatus::Timeout
+        };
+        Err(SendError::new(status, value))
+    }
 
-                return ChannelStatus::Success;
+    pub fn try_push(&mut self, value: T) -> Result<(), SendError<T>> {
+        if self.is_closed() {
+            Err(SendError::new(self.closed_status(), value))
+        } else if !self.can_put() {
+            Err(SendError::new(ChannelStatus::Full, value))
+        } else if self.is_rendezvous() {
+            // Hand the value over only to a receiver parked in `recv`, it takes the value as soon as it runs.
+            match claim_one(&mut self.waiting_consumers) {
+                Some(ctx) => {
+                    self.queue.push_back(value);
+                    Context::resume(ctx);
+                    Ok(())
+                }
+                None => Err(SendError::new(ChannelStatus::Full, value)),
             }
+        } else {
+            self.put(value);
+            Ok(())
         }
     }
 
     pub fn pop(&mut self) -> Result<T, ChannelStatus> {
+        self.pop_until(None)
+    }
+
+    pub fn pop_until(&mut self, deadline: Option<Instant>) -> Result<T, ChannelStatus> {
         let active_ctx = Context::active();
         loop {
             if self.is_empty_() {
                 if self.is_closed() {
-                    return Err(ChannelStatus::Closed);
-                } else {
-                    // Empty? Suspend until sender will send value.
-                    self.waiting_consumers.push_back(active_ctx);
-                    active_ctx
-                        .twstatus
-                        .store(0 as *mut _, std::sync::atomic::Ordering::Release);
-                    active_ctx.scheduler.get().suspend_thread(active_ctx);
+                    return Err(self.closed_status());
                 }
-            } else {
-                let mut value = None;
-                std::mem::swap(&mut self.slots[self.cidx], &mut value);
-                self.cidx = (self.cidx + 1) % self.capacity;
-
-                while let Some(producer_ctx) = self.waiting_producers.pop_front() {
-                    let expected = self as *const ChannelInner<T>;
-                    let result = producer_ctx.get().twstatus.compare_exchange(
-                        expected as *mut _,
-                        -1i8 as *mut i8,
-                        std::sync::atomic::Ordering::Acquire,
-                        std::sync::atomic::Ordering::Relaxed,
-                    );
-                    match result {
-                        Ok(_) => {
-                            Context::resume(producer_ctx);
-                            break;
-                        }
-                        _ => {
-                            Context::resume(producer_ctx);
-
-                            break;
-                        }
-                    }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(ChannelStatus::Timeout);
                 }
-
-                return Ok(value.unwrap());
+                // Empty? Suspend until sender will send value.
+                self.waiting_consumers.push_back(active_ctx);
+                if self.is_rendezvous() {
+                    // A waiting consumer is what makes a rendezvous send ready, tell a selecting producer.
+                    wake_one(&mut self.waiting_producers);
+                }
+                let what = BlockedOn::ChannelRecv(self as *const Self as usize);
+                if self.park(what, deadline) {
+                    return Err(ChannelStatus::Timeout);
+                }
+            } else {
+                return Ok(self.take());
             }
         }
     }
 
     pub fn try_pop(&mut self) -> Result<T, ChannelStatus> {
-        loop {
-            if self.is_empty_() {
-                if self.is_closed() {
-                    return Err(ChannelStatus::Closed);
-                } else {
-                    return Err(ChannelStatus::Empty);
-                }
+        if self.is_empty_() {
+            if self.is_closed() {
+                Err(self.closed_status())
             } else {
-                let mut value = None;
-                std::mem::swap(&mut self.slots[self.cidx], &mut value);
-                self.cidx = (self.cidx + 1) % self.capacity;
-
-                while let Some(producer_ctx) = self.waiting_producers.pop_front() {
-                    let expected = self as *const ChannelInner<T>;
-                    let result = producer_ctx.get().twstatus.compare_exchange(
-                        expected as *mut _,
-                        -1i8 as *mut i8,
-                        std::sync::atomic::Ordering::Acquire,
-                        std::sync::atomic::Ordering::Relaxed,
-                    );
-                    match result {
-                        Ok(_) => {
-                            Context::resume(producer_ctx);
-                            break;
-                        }
-                        _ => {
-                            Context::resume(producer_ctx);
-                            break;
-                        }
-                    }
-                }
-
-                return Ok(value.unwrap());
+                Err(ChannelStatus::Empty)
             }
+        } else {
+            Ok(self.take())
         }
     }
+
+    /// Put value to channel that is not full and wake up waiting consumer.
+    fn put(&mut self, value: T) {
+        self.queue.push_back(value);
+        self.wake_consumer();
+    }
+
+    /// Wake up a consumer parked in `recv`, or a selecting one if there is none.
+    fn wake_consumer(&mut self) {
+        if !wake_one(&mut self.waiting_consumers) {
+            wake_one(&mut self.selecting_consumers);
+        }
+    }
+
+    /// Take value from non-empty channel and wake up waiting producer.
+    fn take(&mut self) -> T {
+        let value = self.queue.pop_front().unwrap();
+        let sender = std::mem::replace(&mut self.rendezvous_sender, Ptr::null());
+        if !sender.is_null() && sender.claim_wakeup() {
+            Context::resume(sender);
+        }
+        wake_one(&mut self.waiting_producers);
+
+        value
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -251,6 +281,28 @@ pub enum ChannelStatus {
     Full,
     Closed,
     Timeout,
+    /// All senders or all receivers of the channel were dropped.
+    Disconnected,
+}
+
+/// Error returned by a send operation that failed, gives the value back.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SendError<T> {
+    pub status: ChannelStatus,
+    pub value: T,
+}
+
+impl<T> SendError<T> {
+    fn new(status: ChannelStatus, value: T) -> Self {
+        Self { status, value }
+    }
+
+    fn status(result: Result<(), Self>) -> ChannelStatus {
+        match result {
+            Ok(()) => ChannelStatus::Success,
+            Err(err) => err.status,
+        }
+    }
 }
 
 /// A channel is a communication object using which fibers can communicate with each other.
@@ -262,32 +314,31 @@ pub struct Channel<T> {
 }
 use std::rc::Rc;
 impl<T> Channel<T> {
+    /// Creates a channel that buffers up to `size` values.
+    ///
+    /// Zero `size` creates a rendezvous channel: `send` blocks until a receiver takes the value and `try_send` succeeds
+    /// only if a receiver is already waiting.
     pub fn new(size: usize) -> Rc<Self> {
+        Self::with_capacity(size, false)
+    }
+
+    /// Creates a channel without capacity limit, its buffer grows as needed so `send` never blocks.
+    pub fn unbounded() -> Rc<Self> {
+        Self::with_capacity(0, true)
+    }
+
+    fn with_capacity(size: usize, unbounded: bool) -> Rc<Self> {
         Rc::new(Self {
-            inner: Ptr::new(ChannelInner {
-                capacity: size,
-                slots: {
-                    let mut v = Vec::with_capacity(size);
-                    for _ in 0..size {
-                        v.push(None);
-                    }
-                    v
-                },
-                waiting_producers: LinkedList::new(),
-                waiting_consumers: LinkedList::new(),
-                closed: false,
-                cidx: 0,
-                pidx: 0,
-            }),
+            inner: Ptr::new(ChannelInner::new(size, unbounded)),
         })
     }
 
     /// Blocks the current thread until a message is send or the channel is closed.
     ///
     /// If the channel if full and not closed, this call will block until send operation can proceed. If the channel becomes
-    /// closed, this call will wake up and return `ChannelStatus::Closed`.
+    /// closed, this call will wake up and return `ChannelStatus::Closed`. Unbounded channel is never full.
     pub fn send(&self, value: T) -> ChannelStatus {
-        self.inner.get().push(value)
+        SendError::status(self.inner.get().push(value))
     }
     /// Blocks the current thread until a mesasge is received or the channel is empty and closed.
     ///
@@ -297,12 +348,37 @@ impl<T> Channel<T> {
         self.inner.get().pop()
     }
 
+    /// Blocks the current fiber until a message is sent, the channel is closed or `timeout` elapses.
+    ///
+    /// Returns `ChannelStatus::Timeout` if the channel stayed full for the whole `timeout`, the message is dropped in this case.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> ChannelStatus {
+        self.send_deadline(value, Instant::now() + timeout)
+    }
+    /// Blocks the current fiber until a message is sent, the channel is closed or `deadline` passes.
+    ///
+    /// Returns `ChannelStatus::Timeout` if the channel stayed full until `deadline`, the message is dropped in this case.
+    pub fn send_deadline(&self, value: T, deadline: Instant) -> ChannelStatus {
+        SendError::status(self.inner.get().push_until(value, Some(deadline)))
+    }
+    /// Blocks the current fiber until a message is received, the channel is empty and closed or `timeout` elapses.
+    ///
+    /// Returns `Err(ChannelStatus::Timeout)` if no message arrived during `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelStatus> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+    /// Blocks the current fiber until a message is received, the channel is empty and closed or `deadline` passes.
+    ///
+    /// Returns `Err(ChannelStatus::Timeout)` if no message arrived before `deadline`.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, ChannelStatus> {
+        self.inner.get().pop_until(Some(deadline))
+    }
+
     /// Attempts to send a message into the channel without blocking.
     ///
     /// This method will either send a message into the channel immediately or return an error if the channel is full or disconnected. The
     /// returned error contains the original message.
     pub fn try_send(&self, value: T) -> ChannelStatus {
-        self.inner.get().try_push(value)
+        SendError::status(self.inner.get().try_push(value))
     }
 
     /// Attempts to receive a message from the channel without blocking.
@@ -319,11 +395,255 @@ impl<T> Channel<T> {
     pub fn close(&self) {
         self.inner.get().close()
     }
+
+    /// Number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.inner.queue.len()
+    }
+
+    /// Maximum number of values the channel buffers, `None` for unbounded channel.
+    pub fn capacity(&self) -> Option<usize> {
+        if self.inner.unbounded {
+            None
+        } else {
+            Some(self.inner.capacity)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty_()
+    }
+
+    /// Sending would block. Rendezvous channel is full while a value waits for a receiver.
+    pub fn is_full(&self) -> bool {
+        self.inner.is_full_()
+    }
+
+    /// Returns an iterator that receives values, blocking the current fiber until the channel is closed and empty.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.inner,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator that receives values available right now without blocking.
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter {
+            inner: self.inner,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Sending half of a channel created by `channel` or `unbounded`.
+///
+/// Once all senders are dropped, receivers get `ChannelStatus::Disconnected` after taking the values left in the channel.
+pub struct Sender<T> {
+    inner: Ptr<ChannelInner<T>>,
+}
+
+/// Receiving half of a channel created by `channel` or `unbounded`.
+///
+/// Once all receivers are dropped, sending fails with `ChannelStatus::Disconnected`.
+pub struct Receiver<T> {
+    inner: Ptr<ChannelInner<T>>,
+}
+
+/// Creates a channel that buffers up to `size` values and returns its sending and receiving halves.
+///
+/// Zero `size` creates a rendezvous channel, see `Channel::new`.
+pub fn channel<T>(size: usize) -> (Sender<T>, Receiver<T>) {
+    split(ChannelInner::new(size, false))
+}
+
+/// Creates a channel without capacity limit and returns its sending and receiving halves.
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    split(ChannelInner::new(0, true))
+}
+
+fn split<T>(mut inner: ChannelInner<T>) -> (Sender<T>, Receiver<T>) {
+    inner.senders = 1;
+    inner.receivers = 1;
+    let inner = Ptr::new(inner);
+    (Sender { inner }, Receiver { inner })
+}
+
+impl<T> Sender<T> {
+    /// Blocks the current fiber until a message is sent.
+    ///
+    /// Fails if the channel is disconnected, the message is returned back in `SendError`.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.inner.get().push(value)
+    }
+    /// Blocks the current fiber until a message is sent, the channel is disconnected or `timeout` elapses.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendError<T>> {
+        self.send_deadline(value, Instant::now() + timeout)
+    }
+    /// Blocks the current fiber until a message is sent, the channel is disconnected or `deadline` passes.
+    pub fn send_deadline(&self, value: T, deadline: Instant) -> Result<(), SendError<T>> {
+        self.inner.get().push_until(value, Some(deadline))
+    }
+    /// Attempts to send a message without blocking.
+    pub fn try_send(&self, value: T) -> Result<(), SendError<T>> {
+        self.inner.get().try_push(value)
+    }
+    /// All receivers were dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.closed
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Blocks the current fiber until a message is received or the channel is empty and disconnected.
+    pub fn recv(&self) -> Result<T, ChannelStatus> {
+        self.inner.get().pop()
+    }
+    /// Blocks the current fiber until a message is received, the channel is empty and disconnected or `timeout` elapses.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, ChannelStatus> {
+        self.recv_deadline(Instant::now() + timeout)
+    }
+    /// Blocks the current fiber until a message is received, the channel is empty and disconnected or `deadline` passes.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, ChannelStatus> {
+        self.inner.get().pop_until(Some(deadline))
+    }
+    /// Attempts to receive a message without blocking.
+    pub fn try_recv(&self) -> Result<T, ChannelStatus> {
+        self.inner.get().try_pop()
+    }
+    /// All senders were dropped.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.closed
+    }
+    /// Number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.inner.queue.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty_()
+    }
+    /// Returns an iterator that receives values, blocking the current fiber until all senders are dropped and the
+    /// channel is empty.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.inner,
+            marker: PhantomData,
+        }
+    }
+    /// Returns an iterator that receives values available right now without blocking.
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter {
+            inner: self.inner,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Blocking iterator over values received from a channel, see `Channel::iter`.
+pub struct Iter<'a, T> {
+    inner: Ptr<ChannelInner<T>>,
+    marker: PhantomData<&'a ()>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.inner.get().pop().ok()
+    }
+}
+
+/// Non-blocking iterator over values received from a channel, see `Channel::try_iter`.
+pub struct TryIter<'a, T> {
+    inner: Ptr<ChannelInner<T>>,
+    marker: PhantomData<&'a ()>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.inner.get().try_pop().ok()
+    }
+}
+
+/// Blocking iterator that owns the receiver, see `Receiver::iter`.
+pub struct IntoIter<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Channel<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.get().senders += 1;
+        Self { inner: self.inner }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.inner.get().receivers += 1;
+        Self { inner: self.inner }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get();
+        inner.senders -= 1;
+        if inner.senders == 0 {
+            inner.disconnect();
+            if inner.receivers == 0 {
+                let _ = unsafe { Box::from_raw(self.inner.0) };
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get();
+        inner.receivers -= 1;
+        if inner.receivers == 0 {
+            inner.disconnect();
+            if inner.senders == 0 {
+                let _ = unsafe { Box::from_raw(self.inner.0) };
+            }
+        }
+    }
 }
 
 impl<T> Drop for ChannelInner<T> {
     fn drop(&mut self) {
-        self.slots.clear();
+        self.queue.clear();
         self.close();
     }
 }
@@ -333,3 +653,61 @@ impl<T> Drop for Channel<T> {
         let _ = unsafe { Box::from_raw(self.inner.0) };
     }
 }
+
+impl<T> ChannelInner<T> {
+    fn is_ready(&self, op: Operation) -> bool {
+        match op {
+            Operation::Send => self.can_put() || self.closed,
+            Operation::Recv => !self.is_empty_() || self.closed,
+        }
+    }
+
+    fn watch(&mut self, ctx: Ptr<Context>, op: Operation) {
+        match op {
+            Operation::Send => self.waiting_producers.push_back(ctx),
+            Operation::Recv => self.selecting_consumers.push_back(ctx),
+        }
+    }
+
+    fn unwatch(&mut self, ctx: Ptr<Context>, op: Operation) {
+        match op {
+            Operation::Send => unlink_waiter(&mut self.waiting_producers, ctx),
+            Operation::Recv => unlink_waiter(&mut self.selecting_consumers, ctx),
+        }
+    }
+
+    fn wake_waiter(&mut self, op: Operation) {
+        match op {
+            Operation::Send => {
+                wake_one(&mut self.waiting_producers);
+            }
+            Operation::Recv => self.wake_consumer(),
+        }
+    }
+}
+
+macro_rules! impl_select_handle {
+    ($($handle:ident),*) => {
+        $(
+            impl<T> SelectHandle for $handle<T> {
+                fn is_ready(&self, op: Operation) -> bool {
+                    self.inner.is_ready(op)
+                }
+
+                fn watch(&self, ctx: Ptr<Context>, op: Operation) {
+                    self.inner.get().watch(ctx, op)
+                }
+
+                fn unwatch(&self, ctx: Ptr<Context>, op: Operation) {
+                    self.inner.get().unwatch(ctx, op)
+                }
+
+                fn wake_waiter(&self, op: Operation) {
+                    self.inner.get().wake_waiter(op)
+                }
+            }
+        )*
+    };
+}
+
+impl_select_handle!(Channel, Sender, Receiver);
@@ -3,6 +3,7 @@ use crate::ctx::*;
 use crate::detail::spinlock::*;
 use crate::ptr::*;
 use crate::scheduler::*;
+use std::time::{Duration, Instant};
 /// Synchronization primitive that can be used to block a thread, or multiple threads at the same time,
 /// until another thread both modifies a shared variable (the condition), and notifies the condition_variable.
 pub struct Condvar {
@@ -10,12 +11,18 @@ pub struct Condvar {
     pub(crate) wait_queue_splk: SpinLock,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum CvStatus {
     NoTimeout = 1,
     Timeout,
 }
 
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Condvar {
     pub fn new() -> Self {
         Self {
@@ -30,21 +37,7 @@ impl Condvar {
     /// The thread will be unblocked when notify_all() or notify_one() is executed. It may also be unblocked spuriously.
     /// When unblocked, regardless of the reason, lock is reacquired and wait exits.
     pub fn wait_for_mutex(&self, m: &Mutex) {
-        let active_ctx = RUNTIME.with(|rt| rt.active_ctx);
-        let lk = self.wait_queue_splk.lock();
-        self.wait_queue.get().push_back(active_ctx);
-        active_ctx.get().twstatus.store(
-            self as *const Condvar as *mut i8,
-            std::sync::atomic::Ordering::Release,
-        );
-
-        drop(lk);
-
-        m.unlock();
-
-        active_ctx.scheduler.get().suspend();
-
-        m.lock();
+        self.wait_impl(m, None);
     }
     /// Equivalent to
     /// ```c
@@ -57,65 +50,100 @@ impl Condvar {
             self.wait_for_mutex(m);
         }
     }
+    /// Waits on this condition variable for a notification, timing out after a specified duration.
+    ///
+    /// The semantics of this function are equivalent to `wait_for_mutex` except that the fiber will be blocked for roughly no
+    /// longer than `dur`. Returns `CvStatus::Timeout` if the wait timed out.
+    pub fn wait_timeout(&self, m: &Mutex, dur: Duration) -> CvStatus {
+        self.wait_until(m, Instant::now() + dur)
+    }
+    /// Waits on this condition variable for a notification until `deadline` passes.
+    ///
+    /// Returns `CvStatus::Timeout` if the deadline passed before the fiber was notified.
+    pub fn wait_until(&self, m: &Mutex, deadline: Instant) -> CvStatus {
+        self.wait_impl(m, Some(deadline))
+    }
+    /// Waits on this condition variable until `pred` returns true, timing out after a specified duration.
+    ///
+    /// Returns `CvStatus::Timeout` if `pred` is still false after `dur` elapsed.
+    pub fn wait_timeout_pred(
+        &self,
+        m: &Mutex,
+        dur: Duration,
+        mut pred: impl FnMut() -> bool,
+    ) -> CvStatus {
+        let deadline = Instant::now() + dur;
+        while !pred() {
+            if self.wait_until(m, deadline) == CvStatus::Timeout {
+                return if pred() {
+                    CvStatus::NoTimeout
+                } else {
+                    CvStatus::Timeout
+                };
+            }
+        }
+        CvStatus::NoTimeout
+    }
+
+    fn wait_impl(&self, m: &Mutex, deadline: Option<Instant>) -> CvStatus {
+        let active_ctx = RUNTIME.with(|rt| rt.active_ctx);
+        {
+            let _lk = self.wait_queue_splk.lock();
+            self.wait_queue.get().push_back(active_ctx);
+            active_ctx.get().twstatus.store(
+                self as *const Condvar as *mut i8,
+                std::sync::atomic::Ordering::Release,
+            );
+        }
+
+        m.unlock();
+
+        let what = BlockedOn::Condvar(self as *const Condvar as usize);
+        let status = match deadline {
+            Some(deadline) => {
+                if active_ctx.scheduler.get().park_until(what, deadline) {
+                    let _lk = self.wait_queue_splk.lock();
+                    unlink_waiter(self.wait_queue.get(), active_ctx);
+                    CvStatus::Timeout
+                } else {
+                    CvStatus::NoTimeout
+                }
+            }
+            None => {
+                active_ctx.scheduler.get().park(what);
+                CvStatus::NoTimeout
+            }
+        };
+
+        m.lock();
+        status
+    }
     /// If any threads are waiting on this condvar, calling notify_one unblocks one of the waiting threads.
     pub fn notify_one(&self) {
         let active_ctx = RUNTIME.with(|rt| rt.get().active_ctx);
-        let lk = self.wait_queue_splk.lock();
+        let _lk = self.wait_queue_splk.lock();
         while let Some(ctx) = self.wait_queue.get().pop_front() {
-            let expected = self as *const Condvar as *mut i8;
-            let result = ctx.get().twstatus.compare_exchange(
-                expected,
-                -1i8 as *mut i8,
-                std::sync::atomic::Ordering::Acquire,
-                std::sync::atomic::Ordering::Relaxed,
-            );
-            match result {
-                Ok(_) => {
-                    active_ctx.scheduler.get().resume(ctx);
-
-                    break;
-                }
-                Err(x) => {
-                    if x.is_null() {
-                        active_ctx.scheduler.get().resume(ctx);
-                    }
-                    break;
-                }
+            // Skip fibers whose wait already timed out.
+            if ctx.claim_wakeup() {
+                active_ctx.scheduler.get().resume(ctx);
+                break;
             }
         }
-        drop(lk);
     }
     /// Unblocks all threads currently waiting for this condvar.
     pub fn notify_all(&self) {
         let active_ctx = RUNTIME.with(|rt| rt.get().active_ctx);
-        let lk = self.wait_queue_splk.lock();
+        let _lk = self.wait_queue_splk.lock();
         while let Some(ctx) = self.wait_queue.get().pop_front() {
-            let expected = self as *const Condvar as *mut i8;
-            let result = ctx.get().twstatus.compare_exchange(
-                expected,
-                -1i8 as *mut i8,
-                std::sync::atomic::Ordering::Acquire,
-                std::sync::atomic::Ordering::Relaxed,
-            );
-            match result {
-                Ok(_) => {
-                    active_ctx.scheduler.get().resume(ctx);
-                    break;
-                }
-                Err(x) => {
-                    if x.is_null() {
-                        active_ctx.scheduler.get().resume(ctx);
-                        break;
-                    }
-                }
+            if ctx.claim_wakeup() {
+                active_ctx.scheduler.get().resume(ctx);
             }
         }
-        drop(lk);
     }
 }
 
 impl Drop for Condvar {
     fn drop(&mut self) {
-        unsafe { std::intrinsics::drop_in_place(self.wait_queue.0) }
+        unsafe { std::ptr::drop_in_place(self.wait_queue.0) }
     }
 }
@@ -0,0 +1,236 @@
+use crate::ctx::*;
+use crate::detail::spinlock::SpinLock;
+use crate::ptr::*;
+use std::collections::LinkedList;
+use std::time::{Duration, Instant};
+
+struct EventInner {
+    signaled: bool,
+    auto_reset: bool,
+    waiters: LinkedList<Ptr<Context>>,
+    wait_queue_splk: SpinLock,
+}
+
+/// Enqueue active context to `waiters`, the caller holds `splk`.
+fn enqueue(waiters: &mut LinkedList<Ptr<Context>>, addr: usize) {
+    let active_ctx = Context::active();
+    active_ctx
+        .twstatus
+        .store(addr as *mut i8, std::sync::atomic::Ordering::Release);
+    waiters.push_back(active_ctx);
+}
+
+/// Park active context enqueued to `waiters` until it is woken up by `wake_one` or `deadline` passes, returns `false`
+/// on timeout.
+fn park_on(
+    waiters: &mut LinkedList<Ptr<Context>>,
+    splk: &SpinLock,
+    what: BlockedOn,
+    deadline: Option<Instant>,
+) -> bool {
+    let active_ctx = Context::active();
+    let scheduler = active_ctx.scheduler.get();
+    match deadline {
+        Some(deadline) => {
+            if scheduler.park_until(what, deadline) {
+                let _lk = splk.lock();
+                unlink_waiter(waiters, active_ctx);
+                return false;
+            }
+            true
+        }
+        None => {
+            scheduler.park(what);
+            true
+        }
+    }
+}
+
+/// A signal fibers can wait for, without a companion mutex or predicate.
+///
+/// A manual-reset event stays set and lets every waiter through until `reset` is called. An auto-reset event lets
+/// exactly one waiter through per `set` and resets itself.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::Event;
+/// use greenie::*;
+/// create_main(|| {
+///     let ready = Event::manual_reset(false);
+///     let h = spawn_greenie(|ready: Event| ready.wait(), (ready,));
+///     ready.set();
+///     h.join().unwrap();
+///     assert!(ready.is_set());
+/// });
+/// ```
+#[derive(Copy, Clone)]
+pub struct Event {
+    inner: Ptr<EventInner>,
+}
+
+impl Event {
+    /// Creates an event that stays set until `reset` is called.
+    pub fn manual_reset(signaled: bool) -> Self {
+        Self::new(signaled, false)
+    }
+    /// Creates an event that is reset as soon as it releases one waiter.
+    pub fn auto_reset(signaled: bool) -> Self {
+        Self::new(signaled, true)
+    }
+
+    fn new(signaled: bool, auto_reset: bool) -> Self {
+        Self {
+            inner: Ptr::new(EventInner {
+                signaled,
+                auto_reset,
+                waiters: LinkedList::new(),
+                wait_queue_splk: SpinLock::new(()),
+            }),
+        }
+    }
+    /// Sets the event. Manual-reset event wakes up all waiters, auto-reset event wakes up one waiter or stays set until
+    /// the next `wait` if there are none.
+    pub fn set(&self) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.auto_reset {
+            if !wake_one(&mut inner.waiters) {
+                inner.signaled = true;
+            }
+        } else {
+            inner.signaled = true;
+            while wake_one(&mut inner.waiters) {}
+        }
+    }
+    /// Resets the event.
+    pub fn reset(&self) {
+        let _lk = self.inner.wait_queue_splk.lock();
+        self.inner.get().signaled = false;
+    }
+    pub fn is_set(&self) -> bool {
+        self.inner.signaled
+    }
+    /// Blocks the current fiber until the event is set.
+    pub fn wait(&self) {
+        self.wait_impl(None);
+    }
+    /// Blocks the current fiber until the event is set or `timeout` elapses, returns `false` on timeout.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.wait_impl(Some(Instant::now() + timeout))
+    }
+    /// Blocks the current fiber until the event is set or `deadline` passes, returns `false` on timeout.
+    pub fn wait_until(&self, deadline: Instant) -> bool {
+        self.wait_impl(Some(deadline))
+    }
+
+    fn wait_impl(&self, deadline: Option<Instant>) -> bool {
+        let inner = self.inner.get();
+        let addr = self.inner.0 as usize;
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            if inner.signaled {
+                if inner.auto_reset {
+                    inner.signaled = false;
+                }
+                return true;
+            } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            enqueue(&mut inner.waiters, addr);
+        }
+        park_on(
+            &mut inner.waiters,
+            &inner.wait_queue_splk,
+            BlockedOn::Event(addr),
+            deadline,
+        )
+    }
+}
+
+struct NotifyInner {
+    permit: bool,
+    waiters: LinkedList<Ptr<Context>>,
+    wait_queue_splk: SpinLock,
+}
+
+/// Notifies fibers waiting for an event, similar to tokio's `Notify`.
+///
+/// `notify_one` wakes up one waiter, or stores a single permit that makes the next `notified` call return immediately
+/// if nobody waits. `notify_waiters` wakes up all current waiters and stores no permit.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::Notify;
+/// use greenie::*;
+/// create_main(|| {
+///     let notify = Notify::new();
+///     notify.notify_one();
+///     notify.notified();
+///     assert!(!notify.notified_timeout(std::time::Duration::from_millis(1)));
+/// });
+/// ```
+#[derive(Copy, Clone)]
+pub struct Notify {
+    inner: Ptr<NotifyInner>,
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Self {
+            inner: Ptr::new(NotifyInner {
+                permit: false,
+                waiters: LinkedList::new(),
+                wait_queue_splk: SpinLock::new(()),
+            }),
+        }
+    }
+    /// Wakes up one waiting fiber, or stores a permit for the next `notified` call.
+    pub fn notify_one(&self) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if !wake_one(&mut inner.waiters) {
+            inner.permit = true;
+        }
+    }
+    /// Wakes up all fibers waiting right now.
+    pub fn notify_waiters(&self) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        while wake_one(&mut inner.waiters) {}
+    }
+    /// Blocks the current fiber until it is notified, consumes the stored permit if there is one.
+    pub fn notified(&self) {
+        self.notified_impl(None);
+    }
+    /// Same as `notified` but gives up after `timeout`, returns `false` in this case.
+    pub fn notified_timeout(&self, timeout: Duration) -> bool {
+        self.notified_impl(Some(Instant::now() + timeout))
+    }
+
+    fn notified_impl(&self, deadline: Option<Instant>) -> bool {
+        let inner = self.inner.get();
+        let addr = self.inner.0 as usize;
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            if inner.permit {
+                inner.permit = false;
+                return true;
+            } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            enqueue(&mut inner.waiters, addr);
+        }
+        park_on(
+            &mut inner.waiters,
+            &inner.wait_queue_splk,
+            BlockedOn::Notify(addr),
+            deadline,
+        )
+    }
+}
@@ -2,10 +2,91 @@ use crate::ctx::*;
 use crate::detail::spinlock::SpinLock;
 use crate::ptr::*;
 use crate::scheduler::*;
+use std::time::{Duration, Instant};
+
+/// With `Fairness::Eventual` the mutex is handed off directly at least once per this interval.
+const FAIR_TIMEOUT: Duration = Duration::from_micros(500);
+
+/// How `unlock` passes the mutex to waiting fibers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fairness {
+    /// The mutex is released and the first waiter is woken up to race for it, so a running fiber can acquire it again
+    /// before the waiter gets to run. Best throughput, but waiters may starve.
+    Barging,
+    /// Ownership is handed off to the first waiter directly, fibers acquire the mutex in FIFO order.
+    Fifo,
+    /// Same as `Barging`, but ownership is handed off directly if it was not for `FAIR_TIMEOUT`, like in parking_lot.
+    Eventual,
+}
+
 struct MutexInner {
     pub(crate) owner: Ptr<Context>,
     pub(crate) wait_queue: std::collections::LinkedList<crate::ptr::Ptr<Context>>,
     pub(crate) wait_queue_splk: SpinLock,
+    fairness: Fairness,
+    /// Next time `Fairness::Eventual` mutex is handed off.
+    fair_at: Instant,
+    #[cfg(feature = "lockdep")]
+    pub(crate) class: crate::detail::lockdep::LockClass,
+}
+
+impl MutexInner {
+    /// Make `ctx` the owner, it inherits priority of fibers still waiting.
+    fn set_owner(&mut self, ctx: Ptr<Context>) {
+        let addr = self as *const Self as usize;
+        self.owner = ctx;
+        ctx.get().owned_mutexes.push(addr);
+        if let Some(priority) = self.wait_queue.iter().map(|waiter| waiter.priority()).max() {
+            inherit_priority(addr, priority);
+        }
+    }
+
+    /// Unlocking fiber must pass ownership to the first waiter instead of releasing the mutex.
+    fn handoff(&mut self) -> bool {
+        match self.fairness {
+            Fairness::Barging => false,
+            Fairness::Fifo => true,
+            Fairness::Eventual => {
+                let now = Instant::now();
+                if now < self.fair_at {
+                    return false;
+                }
+                self.fair_at = now + FAIR_TIMEOUT;
+                true
+            }
+        }
+    }
+}
+
+/// Owner of the mutex identified by `addr` in `BlockedOn::Mutex`.
+pub(crate) fn owner_of(addr: usize) -> Ptr<Context> {
+    unsafe { (*(addr as *const MutexInner)).owner }
+}
+
+/// Raise priority of the owner of mutex `addr`, and of owners of mutexes it waits on in turn, to `priority`.
+pub(crate) fn inherit_priority(addr: usize, priority: i32) {
+    let mut owner = owner_of(addr);
+    while !owner.is_null() && owner.priority() < priority {
+        let old = owner.priority();
+        owner.get().inherited_priority = Some(priority);
+        Context::priority_updated(owner, old);
+        match owner.blocked_on {
+            BlockedOn::Mutex(addr) => owner = owner_of(addr),
+            _ => break,
+        }
+    }
+}
+
+/// Recompute priority `ctx` inherits from fibers waiting on mutexes it holds, e.g. after it released one of them.
+fn restore_priority(ctx: Ptr<Context>) {
+    let old = ctx.priority();
+    ctx.get().inherited_priority = ctx
+        .owned_mutexes
+        .iter()
+        .flat_map(|addr| unsafe { (*(*addr as *const MutexInner)).wait_queue.iter() })
+        .map(|waiter| waiter.priority())
+        .max();
+    Context::priority_updated(ctx, old);
 }
 
 ///A mutual exclusion primitive useful for protecting shared data
@@ -17,22 +98,31 @@ pub struct Mutex {
     inner: Ptr<MutexInner>,
 }
 
-impl Drop for Mutex {
-    fn drop(&mut self) {
-        unsafe {
-            std::intrinsics::drop_in_place(self.inner.0);
-        }
+impl Default for Mutex {
+    #[track_caller]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Mutex {
-    /// Creates a new mutex in an unlocked state ready for use.
+    /// Creates a new mutex in an unlocked state ready for use, it uses `Fairness::Barging`.
+    #[track_caller]
     pub fn new() -> Self {
+        Self::with_fairness(Fairness::Barging)
+    }
+    /// Creates a new mutex in an unlocked state that passes ownership to waiters according to `fairness`.
+    #[track_caller]
+    pub fn with_fairness(fairness: Fairness) -> Self {
         Self {
             inner: Ptr::new(MutexInner {
                 owner: Ptr::null(),
                 wait_queue: std::collections::LinkedList::new(),
                 wait_queue_splk: SpinLock::new(()),
+                fairness,
+                fair_at: Instant::now() + FAIR_TIMEOUT,
+                #[cfg(feature = "lockdep")]
+                class: std::panic::Location::caller(),
             }),
         }
     }
@@ -42,44 +132,141 @@ impl Mutex {
     /// the lock held.
     ///
     /// ## Panics
-    /// Panics if deadlock found
+    /// Panics if the mutex is already held by the current fiber. In debug builds also panics if waiting would close a
+    /// cycle of fibers waiting on mutexes held by each other.
     pub fn lock(&self) {
+        self.lock_impl(None);
+    }
+    /// Attempts to acquire this lock, blocking the current fiber for roughly no longer than `dur`.
+    ///
+    /// Returns `false` if the lock could not be acquired before the timeout.
+    ///
+    /// ## Panics
+    /// Panics if the mutex is already held by the current fiber
+    pub fn try_lock_for(&self, dur: Duration) -> bool {
+        self.try_lock_until(Instant::now() + dur)
+    }
+    /// Attempts to acquire this lock, blocking the current fiber until `deadline` passes.
+    ///
+    /// Returns `false` if the lock could not be acquired before the deadline.
+    ///
+    /// ## Panics
+    /// Panics if the mutex is already held by the current fiber
+    pub fn try_lock_until(&self, deadline: Instant) -> bool {
+        self.lock_impl(Some(deadline))
+    }
+
+    fn lock_impl(&self, deadline: Option<Instant>) -> bool {
         let inner = self.inner.get();
+        let what = BlockedOn::Mutex(self.inner.0 as usize);
         loop {
             let active_ctx = RUNTIME.with(|rt| rt.get().active_ctx);
 
-            let lk = self.inner.wait_queue_splk.lock();
-            if active_ctx == inner.owner {
-                panic!("greenie: deadlock detected");
-            } else if inner.owner.is_null() {
-                inner.owner = active_ctx;
-                return;
+            {
+                let _lk = self.inner.wait_queue_splk.lock();
+                if active_ctx == inner.owner {
+                    panic!("greenie: deadlock detected");
+                } else if inner.owner.is_null() {
+                    inner.set_owner(active_ctx);
+                    #[cfg(feature = "lockdep")]
+                    crate::detail::lockdep::acquired(inner.class);
+                    return true;
+                }
+                if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                    return false;
+                }
+                // A timed wait ends by itself, it can't deadlock.
+                #[cfg(debug_assertions)]
+                if deadline.is_none() {
+                    self.check_wait_cycle(active_ctx);
+                }
+                inner.wait_queue.push_back(active_ctx);
+                active_ctx.twstatus.store(
+                    self.inner.0 as *mut i8,
+                    std::sync::atomic::Ordering::Release,
+                );
+                inherit_priority(self.inner.0 as usize, active_ctx.priority());
+            }
+
+            let scheduler = active_ctx.scheduler.get();
+            match deadline {
+                Some(deadline) => {
+                    if scheduler.park_until(what, deadline) {
+                        let _lk = self.inner.wait_queue_splk.lock();
+                        unlink_waiter(&mut inner.wait_queue, active_ctx);
+                        if !inner.owner.is_null() {
+                            restore_priority(inner.owner);
+                        }
+                        return false;
+                    }
+                }
+                None => scheduler.park(what),
+            }
+            if inner.owner == active_ctx {
+                // Handed off by `unlock`.
+                #[cfg(feature = "lockdep")]
+                crate::detail::lockdep::acquired(inner.class);
+                return true;
             }
-            inner.wait_queue.push_back(active_ctx);
-            RUNTIME.with(|rt| {
-                rt.get().suspend_thread_not_yield(rt.active_ctx);
-                drop(lk);
-                rt.get().switch_without_current();
-            });
         }
     }
+
+    /// Follow the chain of mutex owners starting at this mutex and panic if it leads back to `waiter`.
+    ///
+    /// ## Panics
+    /// Panics with ids of every fiber in the cycle and addresses of the mutexes they wait on
+    #[cfg(debug_assertions)]
+    fn check_wait_cycle(&self, waiter: Ptr<Context>) {
+        let mut chain = vec![(self.inner.0 as usize, self.inner.owner)];
+        let mut owner = self.inner.owner;
+        while !owner.is_null() && owner != waiter {
+            match owner.blocked_on {
+                // A timed wait such as `try_lock_for` ends by itself, so the chain is not a deadlock.
+                BlockedOn::Mutex(_) if owner.sleep_deadline.is_some() => return,
+                BlockedOn::Mutex(addr) => {
+                    let next = owner_of(addr);
+                    if chain.iter().any(|(_, ctx)| *ctx == next) {
+                        // Cycle that does not involve `waiter`, it is reported by whoever closed it.
+                        return;
+                    }
+                    chain.push((addr, next));
+                    owner = next;
+                }
+                _ => return,
+            }
+        }
+        if owner.is_null() {
+            return;
+        }
+        let mut report = String::from("greenie: deadlock detected:");
+        let mut ctx = waiter;
+        for (addr, holder) in chain.iter() {
+            report.push_str(&format!(
+                " fiber #{} waits on mutex {:#x} held by fiber #{};",
+                ctx.id, addr, holder.id
+            ));
+            ctx = *holder;
+        }
+        report.pop();
+        panic!("{}", report);
+    }
     /// Attempts to acquire this lock.
     ///
     /// If the lock could not be acquired at this time, then `false` is returned. Otherwise, `true` is returned.
-    /// This function does not block.
+    /// This function neither blocks nor yields.
     pub fn try_lock(&self) -> bool {
         let active_ctx = RUNTIME.with(|rt| rt.get().active_ctx);
         let inner = self.inner.get();
-        let lk = self.inner.wait_queue_splk.lock();
+        let _lk = self.inner.wait_queue_splk.lock();
         if active_ctx == inner.owner {
             panic!("greenie: deadlock detected");
         } else if inner.owner.is_null() {
-            inner.owner = active_ctx;
+            inner.set_owner(active_ctx);
+            #[cfg(feature = "lockdep")]
+            crate::detail::lockdep::acquired(inner.class);
+            return true;
         }
-        drop(lk);
-
-        yield_thread();
-        active_ctx == inner.owner
+        false
     }
     /// Unlock current mutex.
     ///
@@ -88,17 +275,29 @@ impl Mutex {
     pub fn unlock(&self) {
         let inner = self.inner.get();
         let active_ctx = RUNTIME.with(|rt| rt.get().active_ctx);
-        let lk = self.inner.wait_queue_splk.lock();
+        let _lk = self.inner.wait_queue_splk.lock();
         if active_ctx != inner.owner {
             panic!("greenie: no privilege to perform the operation");
         }
+        #[cfg(feature = "lockdep")]
+        crate::detail::lockdep::released(inner.class);
 
         inner.owner = Ptr::null();
+        let owned = &mut active_ctx.get().owned_mutexes;
+        if let Some(pos) = owned.iter().position(|addr| *addr == self.inner.0 as usize) {
+            owned.swap_remove(pos);
+        }
         if !inner.wait_queue.is_empty() {
-            let ctx = inner.wait_queue.pop_front().unwrap();
-            //ctx.get().state = State::Ready;
-            Context::resume(ctx);
+            // Skips fibers whose `try_lock_for` already timed out.
+            if inner.handoff() {
+                if let Some(ctx) = claim_one(&mut inner.wait_queue) {
+                    inner.set_owner(ctx);
+                    Context::resume(ctx);
+                }
+            } else {
+                wake_one(&mut inner.wait_queue);
+            }
         }
-        drop(lk);
+        restore_priority(active_ctx);
     }
 }
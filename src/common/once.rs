@@ -0,0 +1,213 @@
+//! One-time initialization that parks fibers instead of blocking the thread.
+
+use crate::ctx::*;
+use crate::detail::spinlock::{const_spinlock, SpinLock};
+use crate::ptr::*;
+use std::cell::{Cell, UnsafeCell};
+use std::collections::LinkedList;
+use std::sync::atomic::{AtomicPtr, AtomicU8, Ordering};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// A synchronization primitive which can be used to run a one-time initialization.
+///
+/// Unlike `std::sync::Once`, fibers that call `call_once` while another fiber runs the initialization (which may yield)
+/// are parked until it finishes. If the initialization panics, the next caller runs it again.
+///
+/// Only fibers of the thread running the initialization are parked, callers from other OS threads spin with
+/// `std::thread::yield_now` until it is done.
+pub struct Once {
+    state: AtomicU8,
+    /// Scheduler of the fiber running the initialization, only fibers of this scheduler may park on `waiters`.
+    runner: AtomicPtr<crate::scheduler::Scheduler>,
+    waiters: UnsafeCell<LinkedList<Ptr<Context>>>,
+    wait_queue_splk: SpinLock,
+}
+
+unsafe impl Sync for Once {}
+unsafe impl Send for Once {}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Marks `Once` incomplete again and wakes up waiters if initialization panics.
+struct Finish<'a> {
+    once: &'a Once,
+    state: u8,
+}
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        let _lk = self.once.wait_queue_splk.lock();
+        self.once.state.store(self.state, Ordering::Release);
+        let waiters = unsafe { &mut *self.once.waiters.get() };
+        while let Some(ctx) = waiters.pop_front() {
+            Context::resume(ctx);
+        }
+    }
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            runner: AtomicPtr::new(std::ptr::null_mut()),
+            waiters: UnsafeCell::new(LinkedList::new()),
+            wait_queue_splk: const_spinlock(),
+        }
+    }
+    /// Performs the initialization routine once and only once, the routine is executed only if this is the first time
+    /// `call_once` has been called.
+    ///
+    /// Returns once the initialization is complete, even if it was run by another fiber.
+    pub fn call_once(&self, f: impl FnOnce()) {
+        let active_ctx = Context::active();
+        loop {
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    self.runner.store(active_ctx.scheduler.0, Ordering::Relaxed);
+                    let mut finish = Finish {
+                        once: self,
+                        state: INCOMPLETE,
+                    };
+                    f();
+                    finish.state = COMPLETE;
+                    return;
+                }
+                Err(COMPLETE) => return,
+                Err(_) => {}
+            }
+            if self.runner.load(Ordering::Relaxed) != active_ctx.scheduler.0 {
+                std::thread::yield_now();
+                continue;
+            }
+            {
+                let _lk = self.wait_queue_splk.lock();
+                if self.state.load(Ordering::Acquire) != RUNNING {
+                    continue;
+                }
+                unsafe { &mut *self.waiters.get() }.push_back(active_ctx);
+            }
+            active_ctx
+                .scheduler
+                .get()
+                .park(BlockedOn::Once(self as *const Self as usize));
+        }
+    }
+    /// Returns true if some `call_once` call has completed successfully.
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
+
+/// A cell which can be written to only once.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::OnceCell;
+/// use greenie::*;
+/// create_main(|| {
+///     let cell = OnceCell::new();
+///     assert_eq!(cell.get(), None);
+///     assert_eq!(*cell.get_or_init(|| 42), 42);
+///     assert_eq!(cell.set(1), Err(1));
+/// });
+/// ```
+pub struct OnceCell<T> {
+    once: Once,
+    value: UnsafeCell<Option<T>>,
+}
+
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+unsafe impl<T: Send> Send for OnceCell<T> {}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OnceCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            once: Once::new(),
+            value: UnsafeCell::new(None),
+        }
+    }
+    /// Returns the value if the cell is initialized.
+    pub fn get(&self) -> Option<&T> {
+        if self.once.is_completed() {
+            unsafe { (*self.value.get()).as_ref() }
+        } else {
+            None
+        }
+    }
+    /// Returns the value, initializing the cell with `f` if it is empty.
+    ///
+    /// Other fibers calling this method while `f` runs are parked until it finishes, `f` is never run twice.
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        self.once
+            .call_once(|| unsafe { *self.value.get() = Some(f()) });
+        self.get().unwrap()
+    }
+    /// Sets the value of the cell, returns it back if the cell was already initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.once
+            .call_once(|| unsafe { *self.value.get() = value.take() });
+        match value {
+            Some(value) => Err(value),
+            None => Ok(()),
+        }
+    }
+    pub fn into_inner(self) -> Option<T> {
+        self.value.into_inner()
+    }
+}
+
+/// A value which is initialized on the first access.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::Lazy;
+/// use greenie::*;
+/// static ANSWER: Lazy<i32> = Lazy::new(|| 42);
+/// create_main(|| {
+///     assert_eq!(*ANSWER, 42);
+/// });
+/// ```
+pub struct Lazy<T, F = fn() -> T> {
+    cell: OnceCell<T>,
+    init: Cell<Option<F>>,
+}
+
+unsafe impl<T: Send + Sync, F: Send> Sync for Lazy<T, F> {}
+
+impl<T, F> Lazy<T, F> {
+    pub const fn new(init: F) -> Self {
+        Self {
+            cell: OnceCell::new(),
+            init: Cell::new(Some(init)),
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T> std::ops::Deref for Lazy<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.cell.get_or_init(|| match self.init.take() {
+            Some(init) => init(),
+            None => panic!("greenie: Lazy instance has previously been poisoned"),
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use crate::ctx::*;
+use crate::detail::spinlock::SpinLock;
+use crate::ptr::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Poll, Waker};
+
+struct OneshotInner<T> {
+    value: Option<T>,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+    /// Fiber parked in `recv`.
+    waiter: Ptr<Context>,
+    /// Task polling the receiver as a future.
+    waker: Option<Waker>,
+    wait_queue_splk: SpinLock,
+}
+
+impl<T> OneshotInner<T> {
+    fn wake(&mut self) {
+        let waiter = std::mem::replace(&mut self.waiter, Ptr::null());
+        if !waiter.is_null() {
+            Context::resume(waiter);
+        }
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn release(inner: Ptr<Self>) {
+        if inner.sender_dropped && inner.receiver_dropped {
+            let _ = unsafe { Box::from_raw(inner.0) };
+        }
+    }
+}
+
+/// Error returned by `OneshotReceiver` when the sender was dropped without sending a value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Canceled;
+
+impl std::fmt::Display for Canceled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "oneshot canceled")
+    }
+}
+
+/// Sending half of a oneshot channel.
+pub struct OneshotSender<T> {
+    inner: Ptr<OneshotInner<T>>,
+}
+
+/// Receiving half of a oneshot channel, can also be awaited as a future.
+pub struct OneshotReceiver<T> {
+    inner: Ptr<OneshotInner<T>>,
+}
+
+/// Creates a channel that transfers a single value.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::{oneshot, OneshotSender};
+/// use greenie::*;
+/// create_main(|| {
+///     let (tx, rx) = oneshot();
+///     spawn_greenie(|tx: OneshotSender<i32>| tx.send(42).unwrap(), (tx,));
+///     assert_eq!(rx.recv(), Ok(42));
+/// });
+/// ```
+pub fn oneshot<T>() -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let inner = Ptr::new(OneshotInner {
+        value: None,
+        sender_dropped: false,
+        receiver_dropped: false,
+        waiter: Ptr::null(),
+        waker: None,
+        wait_queue_splk: SpinLock::new(()),
+    });
+    (OneshotSender { inner }, OneshotReceiver { inner })
+}
+
+impl<T> OneshotSender<T> {
+    /// Sends the value and wakes up the receiver.
+    ///
+    /// Fails if the receiver was dropped, the value is returned back in this case.
+    pub fn send(self, value: T) -> Result<(), T> {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.receiver_dropped {
+            return Err(value);
+        }
+        inner.value = Some(value);
+        inner.wake();
+        Ok(())
+    }
+    /// Receiver was dropped, sending will fail.
+    pub fn is_closed(&self) -> bool {
+        self.inner.receiver_dropped
+    }
+}
+
+impl<T> OneshotReceiver<T> {
+    /// Blocks the current fiber until the value is sent.
+    ///
+    /// Returns `Err(Canceled)` if the sender was dropped without sending a value.
+    pub fn recv(self) -> Result<T, Canceled> {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        loop {
+            {
+                let _lk = self.inner.wait_queue_splk.lock();
+                if let Some(value) = inner.value.take() {
+                    return Ok(value);
+                } else if inner.sender_dropped {
+                    return Err(Canceled);
+                }
+                inner.waiter = active_ctx;
+            }
+            active_ctx
+                .scheduler
+                .get()
+                .park(BlockedOn::ChannelRecv(self.inner.0 as usize));
+        }
+    }
+    /// Attempts to receive the value without blocking, returns `Ok(None)` if it was not sent yet.
+    pub fn try_recv(&mut self) -> Result<Option<T>, Canceled> {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        match inner.value.take() {
+            Some(value) => Ok(Some(value)),
+            None if inner.sender_dropped => Err(Canceled),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> Future for OneshotReceiver<T> {
+    type Output = Result<T, Canceled>;
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if let Some(value) = inner.value.take() {
+            Poll::Ready(Ok(value))
+        } else if inner.sender_dropped {
+            Poll::Ready(Err(Canceled))
+        } else {
+            inner.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Drop for OneshotSender<T> {
+    fn drop(&mut self) {
+        let inner = self.inner.get();
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            inner.sender_dropped = true;
+            inner.wake();
+        }
+        OneshotInner::release(self.inner);
+    }
+}
+
+impl<T> Drop for OneshotReceiver<T> {
+    fn drop(&mut self) {
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            self.inner.get().receiver_dropped = true;
+        }
+        OneshotInner::release(self.inner);
+    }
+}
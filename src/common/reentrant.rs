@@ -0,0 +1,106 @@
+use super::mutex;
+use crate::ctx::Context;
+use crate::ptr::Ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+struct ReentrantMutexInner<T> {
+    value: T,
+    mutex: mutex::Mutex,
+    /// Read by any fiber to tell whether it already owns the mutex. Relaxed is enough: a fiber can only see itself here
+    /// if it stored itself, other fibers see some other value and go to `mutex`.
+    owner: AtomicPtr<Context>,
+    /// Only touched by the owner.
+    count: usize,
+}
+
+/// A mutex that can be locked several times by the fiber that owns it.
+///
+/// Every `lock` by the owning fiber returns another guard, the mutex is released when the outermost guard is dropped.
+/// Guards give shared access only since several of them may exist at once, use `Cell` or `RefCell` for mutation.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::ReentrantMutex;
+/// use greenie::*;
+/// create_main(|| {
+///     let m = ReentrantMutex::new(std::cell::Cell::new(1));
+///     let outer = m.lock();
+///     let inner = m.lock();
+///     inner.set(2);
+///     drop(inner);
+///     assert_eq!(outer.get(), 2);
+/// });
+/// ```
+pub struct ReentrantMutex<T> {
+    inner: Ptr<ReentrantMutexInner<T>>,
+}
+
+impl<T> Clone for ReentrantMutex<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner }
+    }
+}
+
+impl<T> ReentrantMutex<T> {
+    /// Creates a new reentrant mutex in an unlocked state ready for use.
+    #[track_caller]
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Ptr::new(ReentrantMutexInner {
+                value,
+                mutex: mutex::Mutex::new(),
+                owner: AtomicPtr::new(std::ptr::null_mut()),
+                count: 0,
+            }),
+        }
+    }
+    /// Acquires the mutex, blocking the current fiber unless it already owns the mutex.
+    pub fn lock(&self) -> ReentrantMutexGuard<'_, T> {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        if inner.owner.load(Ordering::Relaxed) != active_ctx.0 {
+            inner.mutex.lock();
+            inner.owner.store(active_ctx.0, Ordering::Relaxed);
+        }
+        inner.count += 1;
+        ReentrantMutexGuard { mutex: self }
+    }
+    /// Attempts to acquire the mutex without blocking.
+    ///
+    /// Always succeeds if the current fiber already owns the mutex.
+    pub fn try_lock(&self) -> Option<ReentrantMutexGuard<'_, T>> {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        if inner.owner.load(Ordering::Relaxed) != active_ctx.0 {
+            if !inner.mutex.try_lock() {
+                return None;
+            }
+            inner.owner.store(active_ctx.0, Ordering::Relaxed);
+        }
+        inner.count += 1;
+        Some(ReentrantMutexGuard { mutex: self })
+    }
+}
+
+/// RAII guard of `ReentrantMutex`, the mutex is released when the last guard of the owning fiber is dropped.
+pub struct ReentrantMutexGuard<'a, T> {
+    mutex: &'a ReentrantMutex<T>,
+}
+
+impl<T> std::ops::Deref for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.mutex.inner.value
+    }
+}
+
+impl<T> Drop for ReentrantMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let inner = self.mutex.inner.get();
+        inner.count -= 1;
+        if inner.count == 0 {
+            inner.owner.store(std::ptr::null_mut(), Ordering::Relaxed);
+            inner.mutex.unlock();
+        }
+    }
+}
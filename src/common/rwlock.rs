@@ -0,0 +1,147 @@
+use crate::ctx::*;
+use crate::detail::spinlock::SpinLock;
+use crate::ptr::*;
+use std::collections::LinkedList;
+
+struct RwLockInner {
+    readers: usize,
+    writer: Ptr<Context>,
+    waiting_readers: LinkedList<Ptr<Context>>,
+    waiting_writers: LinkedList<Ptr<Context>>,
+    wait_queue_splk: SpinLock,
+}
+
+/// A reader-writer lock
+///
+/// This type of lock allows a number of readers or at most one writer at any point in time. Waiting fibers are parked
+/// and woken up by the unlocking fiber which hands the lock over to them directly.
+///
+/// Writers are preferred: once a writer waits for the lock, new readers wait until all queued writers are done.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct RwLock {
+    inner: Ptr<RwLockInner>,
+}
+
+impl Default for RwLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RwLock {
+    /// Creates a new instance of an `RwLock` which is unlocked.
+    pub fn new() -> Self {
+        Self {
+            inner: Ptr::new(RwLockInner {
+                readers: 0,
+                writer: Ptr::null(),
+                waiting_readers: LinkedList::new(),
+                waiting_writers: LinkedList::new(),
+                wait_queue_splk: SpinLock::new(()),
+            }),
+        }
+    }
+    /// Locks this rwlock with shared read access, blocking the current fiber until it can be acquired.
+    ///
+    /// ## Panics
+    /// Panics if the lock is already held for writing by the current fiber
+    pub fn read(&self) {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            if inner.writer.is_null() && inner.waiting_writers.is_empty() {
+                inner.readers += 1;
+                return;
+            } else if inner.writer == active_ctx {
+                panic!("greenie: deadlock detected");
+            }
+            inner.waiting_readers.push_back(active_ctx);
+        }
+        active_ctx
+            .scheduler
+            .get()
+            .park(BlockedOn::RwLockRead(self.inner.0 as usize));
+    }
+    /// Locks this rwlock with exclusive write access, blocking the current fiber until it can be acquired.
+    ///
+    /// ## Panics
+    /// Panics if the lock is already held for writing by the current fiber
+    pub fn write(&self) {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        {
+            let _lk = self.inner.wait_queue_splk.lock();
+            if inner.writer.is_null() && inner.readers == 0 {
+                inner.writer = active_ctx;
+                return;
+            } else if inner.writer == active_ctx {
+                panic!("greenie: deadlock detected");
+            }
+            inner.waiting_writers.push_back(active_ctx);
+        }
+        active_ctx
+            .scheduler
+            .get()
+            .park(BlockedOn::RwLockWrite(self.inner.0 as usize));
+    }
+    /// Attempts to acquire this rwlock with shared read access.
+    ///
+    /// If the access could not be granted at this time, then `false` is returned. This function does not block.
+    pub fn try_read(&self) -> bool {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.writer.is_null() && inner.waiting_writers.is_empty() {
+            inner.readers += 1;
+            true
+        } else {
+            false
+        }
+    }
+    /// Attempts to acquire this rwlock with exclusive write access.
+    ///
+    /// If the access could not be granted at this time, then `false` is returned. This function does not block.
+    pub fn try_write(&self) -> bool {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.writer.is_null() && inner.readers == 0 {
+            inner.writer = Context::active();
+            true
+        } else {
+            false
+        }
+    }
+    /// Release shared read access.
+    pub fn unlock_read(&self) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        inner.readers -= 1;
+        if inner.readers == 0 {
+            if let Some(writer) = inner.waiting_writers.pop_front() {
+                inner.writer = writer;
+                Context::resume(writer);
+            }
+        }
+    }
+    /// Release exclusive write access.
+    ///
+    /// ## Panics
+    /// Panics if somebody tries to unlock rwlock from another fiber
+    pub fn unlock_write(&self) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.writer != Context::active() {
+            panic!("greenie: no privilege to perform the operation");
+        }
+        inner.writer = Ptr::null();
+        if let Some(writer) = inner.waiting_writers.pop_front() {
+            inner.writer = writer;
+            Context::resume(writer);
+        } else {
+            while let Some(reader) = inner.waiting_readers.pop_front() {
+                inner.readers += 1;
+                Context::resume(reader);
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Waiting on multiple channel operations at once.
+//!
+//! `Select` registers the active fiber on the wait lists of every channel operation it was given, parks it and, once woken
+//! up, withdraws it from all lists again. It only reports which operation is ready: the caller completes it with
+//! `try_recv`/`try_send` right away, before the fiber yields. The `select!` macro does that automatically.
+//!
+//! # Example
+//! ```rust
+//! use greenie::common::Channel;
+//! use greenie::*;
+//! create_main(|| {
+//!     let numbers = Channel::<i32>::new(2);
+//!     let words = Channel::<&'static str>::new(2);
+//!     words.send("hello");
+//!
+//!     let result = greenie::select! {
+//!         recv(numbers) -> n => n.unwrap().to_string(),
+//!         recv(words) -> w => w.unwrap().to_string(),
+//!         timeout(std::time::Duration::from_millis(10)) => "timed out".to_string(),
+//!     };
+//!     assert_eq!(result, "hello");
+//! });
+//! ```
+
+use crate::ctx::*;
+use crate::ptr::*;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Operation {
+    Send,
+    Recv,
+}
+
+/// Primitive that can take part in `Select`.
+pub trait SelectHandle {
+    /// Operation can be completed without blocking, including failing because the primitive is closed.
+    fn is_ready(&self, op: Operation) -> bool;
+    /// Put `ctx` to the wait list of `op`.
+    fn watch(&self, ctx: Ptr<Context>, op: Operation);
+    /// Remove `ctx` from the wait list of `op`.
+    fn unwatch(&self, ctx: Ptr<Context>, op: Operation);
+    /// Pass the wakeup for `op` that was consumed by a select which completed another operation to the next waiter.
+    fn wake_waiter(&self, op: Operation);
+}
+
+impl<H: SelectHandle> SelectHandle for Rc<H> {
+    fn is_ready(&self, op: Operation) -> bool {
+        (**self).is_ready(op)
+    }
+    fn watch(&self, ctx: Ptr<Context>, op: Operation) {
+        (**self).watch(ctx, op)
+    }
+    fn unwatch(&self, ctx: Ptr<Context>, op: Operation) {
+        (**self).unwatch(ctx, op)
+    }
+    fn wake_waiter(&self, op: Operation) {
+        (**self).wake_waiter(op)
+    }
+}
+
+/// Waits on a set of channel operations.
+pub struct Select<'a> {
+    ops: Vec<(&'a dyn SelectHandle, Operation)>,
+}
+
+impl<'a> Default for Select<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Select<'a> {
+    pub fn new() -> Self {
+        Self { ops: vec![] }
+    }
+    /// Adds a receive operation on `Channel` or `Receiver` and returns its index.
+    pub fn recv<H: SelectHandle + 'a>(&mut self, chan: &'a H) -> usize {
+        self.ops.push((chan, Operation::Recv));
+        self.ops.len() - 1
+    }
+    /// Adds a send operation on `Channel` or `Sender` and returns its index.
+    pub fn send<H: SelectHandle + 'a>(&mut self, chan: &'a H) -> usize {
+        self.ops.push((chan, Operation::Send));
+        self.ops.len() - 1
+    }
+    /// Returns index of the first operation that is ready, does not block.
+    pub fn try_select(&mut self) -> Option<usize> {
+        self.ops
+            .iter()
+            .position(|(handle, op)| handle.is_ready(*op))
+    }
+    /// Blocks the current fiber until one of the operations becomes ready and returns its index.
+    pub fn select(&mut self) -> usize {
+        self.select_impl(None).unwrap()
+    }
+    /// Same as `select` but gives up after `timeout`, returns `None` in this case.
+    pub fn select_timeout(&mut self, timeout: Duration) -> Option<usize> {
+        self.select_impl(Some(Instant::now() + timeout))
+    }
+    /// Same as `select` but gives up once `deadline` passes, returns `None` in this case.
+    pub fn select_deadline(&mut self, deadline: Instant) -> Option<usize> {
+        self.select_impl(Some(deadline))
+    }
+
+    fn select_impl(&mut self, deadline: Option<Instant>) -> Option<usize> {
+        let active_ctx = Context::active();
+        loop {
+            if let Some(index) = self.try_select() {
+                return Some(index);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return None;
+            }
+            active_ctx.twstatus.store(
+                self as *const Self as *mut i8,
+                std::sync::atomic::Ordering::Release,
+            );
+            for (handle, op) in self.ops.iter() {
+                handle.watch(active_ctx, *op);
+            }
+            let scheduler = active_ctx.scheduler.get();
+            let timed_out = match deadline {
+                Some(deadline) => scheduler.park_until(BlockedOn::Select, deadline),
+                None => {
+                    scheduler.park(BlockedOn::Select);
+                    false
+                }
+            };
+            for (handle, op) in self.ops.iter() {
+                handle.unwatch(active_ctx, *op);
+            }
+            let selected = self.try_select();
+            if let Some(index) = selected {
+                // We may have been woken up by another operation, don't let its wakeup get lost.
+                for (i, (handle, op)) in self.ops.iter().enumerate() {
+                    if i != index && handle.is_ready(*op) {
+                        handle.wake_waiter(*op);
+                    }
+                }
+                return selected;
+            } else if timed_out {
+                return None;
+            }
+        }
+    }
+}
+
+/// Waits on multiple channel operations and completes exactly one of them.
+///
+/// Supported arms:
+/// - `recv(chan) -> result => body` receives from `chan`, `result` is what `chan.try_recv()` returns.
+/// - `send(chan, value) -> result => body` sends `value` to `chan`, `result` is what `chan.try_send(value)` returns. `value` is
+///   evaluated only if this arm is selected.
+/// - `timeout(duration) => body` runs if no operation became ready in time.
+/// - `default => body` runs if no operation is ready right now, select does not block in this case.
+///
+/// If several operations are ready, the first one is selected.
+#[macro_export]
+macro_rules! select {
+    ($($tokens:tt)*) => {
+        $crate::__select_parse!(() () $($tokens)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_parse {
+    (($($ops:tt)*) $fb:tt) => {
+        $crate::__select_build!(($($ops)*) $fb)
+    };
+    (($($ops:tt)*) $fb:tt recv($c:expr) -> $r:pat => $b:block, $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (recv $c, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt recv($c:expr) -> $r:pat => $b:block $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (recv $c, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt recv($c:expr) -> $r:pat => $b:expr, $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (recv $c, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt recv($c:expr) -> $r:pat => $b:expr) => {
+        $crate::__select_parse!(($($ops)* (recv $c, $r, $b)) $fb)
+    };
+    (($($ops:tt)*) $fb:tt send($c:expr, $v:expr) -> $r:pat => $b:block, $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (send $c, $v, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt send($c:expr, $v:expr) -> $r:pat => $b:block $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (send $c, $v, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt send($c:expr, $v:expr) -> $r:pat => $b:expr, $($rest:tt)*) => {
+        $crate::__select_parse!(($($ops)* (send $c, $v, $r, $b)) $fb $($rest)*)
+    };
+    (($($ops:tt)*) $fb:tt send($c:expr, $v:expr) -> $r:pat => $b:expr) => {
+        $crate::__select_parse!(($($ops)* (send $c, $v, $r, $b)) $fb)
+    };
+    ($ops:tt () timeout($d:expr) => $b:block, $($rest:tt)*) => {
+        $crate::__select_parse!($ops (timeout $d, $b) $($rest)*)
+    };
+    ($ops:tt () timeout($d:expr) => $b:block $($rest:tt)*) => {
+        $crate::__select_parse!($ops (timeout $d, $b) $($rest)*)
+    };
+    ($ops:tt () timeout($d:expr) => $b:expr, $($rest:tt)*) => {
+        $crate::__select_parse!($ops (timeout $d, $b) $($rest)*)
+    };
+    ($ops:tt () timeout($d:expr) => $b:expr) => {
+        $crate::__select_parse!($ops (timeout $d, $b))
+    };
+    ($ops:tt () default => $b:block, $($rest:tt)*) => {
+        $crate::__select_parse!($ops (default $b) $($rest)*)
+    };
+    ($ops:tt () default => $b:block $($rest:tt)*) => {
+        $crate::__select_parse!($ops (default $b) $($rest)*)
+    };
+    ($ops:tt () default => $b:expr, $($rest:tt)*) => {
+        $crate::__select_parse!($ops (default $b) $($rest)*)
+    };
+    ($ops:tt () default => $b:expr) => {
+        $crate::__select_parse!($ops (default $b))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_build {
+    ($ops:tt ()) => {{
+        let mut __greenie_select = $crate::common::select::Select::new();
+        match $crate::__select_nest!(__greenie_select () $ops) {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        }
+    }};
+    ($ops:tt (timeout $d:expr, $b:expr)) => {{
+        let mut __greenie_select = $crate::common::select::Select::new();
+        match $crate::__select_nest!(__greenie_select (timeout $d) $ops) {
+            Ok(value) => value,
+            Err(None) => $b,
+            Err(Some(_)) => unreachable!(),
+        }
+    }};
+    ($ops:tt (default $b:expr)) => {{
+        let mut __greenie_select = $crate::common::select::Select::new();
+        match $crate::__select_nest!(__greenie_select (default) $ops) {
+            Ok(value) => value,
+            Err(None) => $b,
+            Err(Some(_)) => unreachable!(),
+        }
+    }};
+}
+
+/// Registers operations one by one, each in its own scope, selects in the innermost scope and completes the
+/// selected operation on the way back.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __select_nest {
+    ($sel:ident () ()) => {
+        Err(Some($sel.select()))
+    };
+    ($sel:ident (timeout $d:expr) ()) => {
+        Err($sel.select_timeout($d))
+    };
+    ($sel:ident (default) ()) => {
+        Err($sel.try_select())
+    };
+    ($sel:ident $fb:tt ((recv $c:expr, $r:pat, $b:expr) $($rest:tt)*)) => {{
+        let chan = &$c;
+        let index = $sel.recv(chan);
+        match $crate::__select_nest!($sel $fb ($($rest)*)) {
+            Err(Some(selected)) if selected == index => Ok({
+                let $r = chan.try_recv();
+                $b
+            }),
+            other => other,
+        }
+    }};
+    ($sel:ident $fb:tt ((send $c:expr, $v:expr, $r:pat, $b:expr) $($rest:tt)*)) => {{
+        let chan = &$c;
+        let index = $sel.send(chan);
+        match $crate::__select_nest!($sel $fb ($($rest)*)) {
+            Err(Some(selected)) if selected == index => Ok({
+                let $r = chan.try_send($v);
+                $b
+            }),
+            other => other,
+        }
+    }};
+}
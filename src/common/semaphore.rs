@@ -0,0 +1,135 @@
+use crate::ctx::*;
+use crate::detail::spinlock::SpinLock;
+use crate::ptr::*;
+use std::collections::LinkedList;
+
+struct SemaphoreInner {
+    permits: usize,
+    waiters: LinkedList<(Ptr<Context>, usize)>,
+    wait_queue_splk: SpinLock,
+}
+
+/// A counting semaphore.
+///
+/// Semaphore holds a number of permits which fibers acquire and release. Fibers waiting for permits are parked
+/// and served in FIFO order, a large request at the head of the queue is not overtaken by smaller ones.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::Semaphore;
+/// use greenie::*;
+/// create_main(|| {
+///     let sem = Semaphore::new(2);
+///     let permit = sem.acquire(2);
+///     assert!(sem.try_acquire(1).is_none());
+///     drop(permit);
+///     assert_eq!(sem.available_permits(), 2);
+/// });
+/// ```
+#[derive(Copy, Clone)]
+pub struct Semaphore {
+    inner: Ptr<SemaphoreInner>,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with the initial number of permits.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            inner: Ptr::new(SemaphoreInner {
+                permits,
+                waiters: LinkedList::new(),
+                wait_queue_splk: SpinLock::new(()),
+            }),
+        }
+    }
+    /// Acquires `n` permits, blocking the current fiber until they are available.
+    ///
+    /// Permits are returned to the semaphore when returned `SemaphorePermit` is dropped.
+    pub fn acquire(&self, n: usize) -> SemaphorePermit {
+        let inner = self.inner.get();
+        let active_ctx = Context::active();
+        let acquired = {
+            let _lk = self.inner.wait_queue_splk.lock();
+            if inner.waiters.is_empty() && inner.permits >= n {
+                inner.permits -= n;
+                true
+            } else {
+                inner.waiters.push_back((active_ctx, n));
+                false
+            }
+        };
+        if !acquired {
+            // Permits are handed over by `release`.
+            active_ctx
+                .scheduler
+                .get()
+                .park(BlockedOn::Semaphore(self.inner.0 as usize));
+        }
+        SemaphorePermit {
+            semaphore: *self,
+            permits: n,
+        }
+    }
+    /// Tries to acquire `n` permits without blocking.
+    ///
+    /// Returns `None` if there are not enough permits or other fibers are already waiting for permits.
+    pub fn try_acquire(&self, n: usize) -> Option<SemaphorePermit> {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        if inner.waiters.is_empty() && inner.permits >= n {
+            inner.permits -= n;
+            Some(SemaphorePermit {
+                semaphore: *self,
+                permits: n,
+            })
+        } else {
+            None
+        }
+    }
+    /// Adds `n` permits to the semaphore and wakes up waiting fibers that can be satisfied.
+    pub fn release(&self, n: usize) {
+        let inner = self.inner.get();
+        let _lk = self.inner.wait_queue_splk.lock();
+        inner.permits += n;
+        while let Some(&(ctx, wanted)) = inner.waiters.front() {
+            if wanted > inner.permits {
+                break;
+            }
+            inner.permits -= wanted;
+            inner.waiters.pop_front();
+            Context::resume(ctx);
+        }
+    }
+    /// Returns the current number of available permits.
+    pub fn available_permits(&self) -> usize {
+        let _lk = self.inner.wait_queue_splk.lock();
+        self.inner.permits
+    }
+}
+
+/// RAII guard holding permits acquired from a `Semaphore`.
+///
+/// Permits are released back to the semaphore when the guard is dropped.
+pub struct SemaphorePermit {
+    semaphore: Semaphore,
+    permits: usize,
+}
+
+impl SemaphorePermit {
+    /// Number of permits held by this guard.
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+    /// Forgets the permits without releasing them back to the semaphore.
+    pub fn forget(mut self) {
+        self.permits = 0;
+    }
+}
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        if self.permits != 0 {
+            self.semaphore.release(self.permits);
+        }
+    }
+}
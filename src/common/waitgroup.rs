@@ -0,0 +1,100 @@
+use crate::ptr::Ptr;
+
+struct WaitGroupInner {
+    count: usize,
+    mtx: super::mutex::Mutex,
+    cnd: super::condvar::Condvar,
+}
+
+/// A wait group waits for a dynamic set of fibers to finish.
+///
+/// Each fiber is registered with `add` (or `register`) before it starts and calls `done` (or drops its `WaitGuard`) when
+/// it finishes, `wait` blocks until the counter drops to zero.
+///
+/// # Example
+/// ```rust
+/// use greenie::common::{WaitGroup, WaitGuard};
+/// use greenie::*;
+/// create_main(|| {
+///     let wg = WaitGroup::new();
+///     for _ in 0..4 {
+///         spawn_greenie(|_guard: WaitGuard| yield_thread(), (wg.register(),));
+///     }
+///     wg.wait();
+///     assert_eq!(wg.count(), 0);
+/// });
+/// ```
+#[derive(Copy, Clone)]
+pub struct WaitGroup {
+    inner: Ptr<WaitGroupInner>,
+}
+
+impl Default for WaitGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaitGroup {
+    /// Creates a new wait group with zero counter.
+    pub fn new() -> Self {
+        Self {
+            inner: Ptr::new(WaitGroupInner {
+                count: 0,
+                cnd: super::condvar::Condvar::new(),
+                mtx: super::mutex::Mutex::new(),
+            }),
+        }
+    }
+    /// Adds `n` to the counter.
+    pub fn add(&self, n: usize) {
+        self.inner.mtx.lock();
+        self.inner.get().count += n;
+        self.inner.mtx.unlock();
+    }
+    /// Decrements the counter and wakes up waiting fibers once it reaches zero.
+    ///
+    /// ## Panics
+    /// Panics if the counter is already zero
+    pub fn done(&self) {
+        self.inner.mtx.lock();
+        if self.inner.count == 0 {
+            self.inner.mtx.unlock();
+            panic!("greenie: WaitGroup::done called more times than WaitGroup::add");
+        }
+        self.inner.get().count -= 1;
+        let finished = self.inner.count == 0;
+        self.inner.mtx.unlock();
+        if finished {
+            self.inner.cnd.notify_all();
+        }
+    }
+    /// Adds one to the counter and returns a guard that calls `done` when dropped.
+    pub fn register(&self) -> WaitGuard {
+        self.add(1);
+        WaitGuard { wait_group: *self }
+    }
+    /// Blocks the current fiber until the counter reaches zero.
+    pub fn wait(&self) {
+        self.inner.mtx.lock();
+        self.inner
+            .cnd
+            .wait_pred(&self.inner.mtx, || self.inner.count == 0);
+        self.inner.mtx.unlock();
+    }
+    /// Current value of the counter.
+    pub fn count(&self) -> usize {
+        self.inner.count
+    }
+}
+
+/// Registration in a `WaitGroup` that calls `WaitGroup::done` when dropped.
+pub struct WaitGuard {
+    wait_group: WaitGroup,
+}
+
+impl Drop for WaitGuard {
+    fn drop(&mut self) {
+        self.wait_group.done();
+    }
+}
@@ -0,0 +1,215 @@
+//! Single-producer, multi-consumer channel that keeps only the latest value.
+//!
+//! Receivers do not get every value sent, they observe the most recent one and can wait until a newer version is
+//! published. The value is guarded by an `RwLock` so any number of `Ref`s can be alive at once, waiting for a new version
+//! is built on `Condvar`.
+//!
+//! # Example
+//! ```rust
+//! use greenie::common::watch;
+//! use greenie::*;
+//! create_main(|| {
+//!     let (tx, mut rx) = watch::channel("initial");
+//!     let h = spawn_greenie(
+//!         |mut rx: watch::Receiver<&'static str>| {
+//!             rx.changed().unwrap();
+//!             *rx.borrow()
+//!         },
+//!         (rx.clone(),),
+//!     );
+//!     tx.send("first").unwrap();
+//!     tx.send("second").unwrap();
+//!     assert_eq!(h.join().unwrap(), "second");
+//!     assert_eq!(*rx.borrow_and_update(), "second");
+//! });
+//! ```
+
+use super::channel::{ChannelStatus, SendError};
+use super::condvar::Condvar;
+use super::mutex::Mutex;
+use super::rwlock::RwLock;
+use crate::ptr::*;
+
+struct Shared<T> {
+    value: T,
+    version: u64,
+    sender_dropped: bool,
+    receivers: usize,
+    /// Guards `value`, held for reading by every `Ref`.
+    lock: RwLock,
+    /// Guards `version`, `sender_dropped` and `receivers`, used with `changed`.
+    mutex: Mutex,
+    changed: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn release(shared: Ptr<Self>) {
+        if shared.sender_dropped && shared.receivers == 0 {
+            let _ = unsafe { Box::from_raw(shared.0) };
+        }
+    }
+}
+
+/// Sending half of a watch channel.
+pub struct Sender<T> {
+    shared: Ptr<Shared<T>>,
+}
+
+/// Receiving half of a watch channel.
+pub struct Receiver<T> {
+    shared: Ptr<Shared<T>>,
+    /// Version of the value this receiver has seen.
+    seen: u64,
+}
+
+/// Reference to the current value, the value cannot be replaced while it is alive.
+///
+/// `Sender::send` waits until all `Ref`s are dropped, so a fiber must not hold a `Ref` across `send`: it would deadlock.
+pub struct Ref<'a, T> {
+    shared: &'a Shared<T>,
+}
+
+impl<'a, T> Ref<'a, T> {
+    fn new(shared: &'a Shared<T>) -> Self {
+        shared.lock.read();
+        Self { shared }
+    }
+}
+
+impl<T> std::ops::Deref for Ref<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.shared.value
+    }
+}
+
+impl<T> Drop for Ref<'_, T> {
+    fn drop(&mut self) {
+        self.shared.lock.unlock_read();
+    }
+}
+
+/// Creates a watch channel holding `initial` value.
+pub fn channel<T>(initial: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Ptr::new(Shared {
+        value: initial,
+        version: 0,
+        sender_dropped: false,
+        receivers: 1,
+        lock: RwLock::new(),
+        mutex: Mutex::new(),
+        changed: Condvar::new(),
+    });
+    (Sender { shared }, Receiver { shared, seen: 0 })
+}
+
+impl<T> Sender<T> {
+    /// Replaces the value and wakes up all fibers waiting in `Receiver::changed`.
+    ///
+    /// Fails with `ChannelStatus::Disconnected` if all receivers were dropped, the value is returned back in this case.
+    /// Blocks until all `Ref`s to the current value are dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let shared = self.shared.get();
+        shared.lock.write();
+        shared.mutex.lock();
+        if shared.receivers == 0 {
+            shared.mutex.unlock();
+            shared.lock.unlock_write();
+            return Err(SendError {
+                status: ChannelStatus::Disconnected,
+                value,
+            });
+        }
+        shared.value = value;
+        shared.version += 1;
+        shared.mutex.unlock();
+        shared.lock.unlock_write();
+        shared.changed.notify_all();
+        Ok(())
+    }
+    /// Returns a reference to the current value.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::new(&self.shared)
+    }
+    /// Creates a new receiver that has seen the current value.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let shared = self.shared.get();
+        shared.receivers += 1;
+        Receiver {
+            shared: self.shared,
+            seen: shared.version,
+        }
+    }
+    /// Number of receivers of the channel.
+    pub fn receiver_count(&self) -> usize {
+        self.shared.receivers
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Returns a reference to the current value without marking it as seen.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::new(&self.shared)
+    }
+    /// Returns a reference to the current value and marks it as seen.
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let value = Ref::new(&self.shared);
+        // The version only changes together with the value, which cannot happen while `value` is alive.
+        self.seen = self.shared.version;
+        value
+    }
+    /// A value this receiver has not seen was sent.
+    pub fn has_changed(&self) -> bool {
+        self.shared.version != self.seen
+    }
+    /// Blocks the current fiber until a value this receiver has not seen is sent, then marks it as seen.
+    ///
+    /// Returns `Err(ChannelStatus::Disconnected)` if the sender was dropped and there is nothing new to see.
+    pub fn changed(&mut self) -> Result<(), ChannelStatus> {
+        let shared = self.shared;
+        let seen = self.seen;
+        shared.mutex.lock();
+        shared.changed.wait_pred(&shared.mutex, || {
+            shared.version != seen || shared.sender_dropped
+        });
+        let result = if shared.version != seen {
+            self.seen = shared.version;
+            Ok(())
+        } else {
+            Err(ChannelStatus::Disconnected)
+        };
+        shared.mutex.unlock();
+        result
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.get().receivers += 1;
+        Self {
+            shared: self.shared,
+            seen: self.seen,
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let shared = self.shared.get();
+        shared.mutex.lock();
+        shared.sender_dropped = true;
+        shared.mutex.unlock();
+        shared.changed.notify_all();
+        Shared::release(self.shared);
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let shared = self.shared.get();
+        shared.mutex.lock();
+        shared.receivers -= 1;
+        shared.mutex.unlock();
+        Shared::release(self.shared);
+    }
+}
@@ -1,11 +1,77 @@
 use std::sync::atomic::AtomicPtr;
+use std::time::{Duration, Instant};
 
 intrusive_adapter!(pub ReadyAdapter = Ptr<Context> : Context {ready_hook: intrusive_collections::LinkedListLink});
 intrusive_adapter!(pub RemoteAdapter = Ptr<Context> : Context {remote_hook: intrusive_collections::LinkedListLink});
 
+/// Synchronization primitive a context is waiting on.
+///
+/// Addresses identify the primitive and are only meant to be compared with each other.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BlockedOn {
+    Nothing,
+    Mutex(usize),
+    Condvar(usize),
+    ChannelSend(usize),
+    ChannelRecv(usize),
+    RwLockRead(usize),
+    RwLockWrite(usize),
+    Semaphore(usize),
+    /// Waiting in `Select` on several channels.
+    Select,
+    /// Waiting for another fiber to finish `Once` initialization.
+    Once(usize),
+    Event(usize),
+    Notify(usize),
+    /// Waiting for the context with given id to terminate.
+    Join(usize),
+    Sleep,
+}
+
+impl std::fmt::Display for BlockedOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockedOn::Nothing => write!(f, "nothing"),
+            BlockedOn::Mutex(addr) => write!(f, "mutex {:#x}", addr),
+            BlockedOn::Condvar(addr) => write!(f, "condvar {:#x}", addr),
+            BlockedOn::ChannelSend(addr) => write!(f, "channel send {:#x}", addr),
+            BlockedOn::ChannelRecv(addr) => write!(f, "channel recv {:#x}", addr),
+            BlockedOn::RwLockRead(addr) => write!(f, "rwlock read {:#x}", addr),
+            BlockedOn::RwLockWrite(addr) => write!(f, "rwlock write {:#x}", addr),
+            BlockedOn::Semaphore(addr) => write!(f, "semaphore {:#x}", addr),
+            BlockedOn::Select => write!(f, "select"),
+            BlockedOn::Once(addr) => write!(f, "once {:#x}", addr),
+            BlockedOn::Event(addr) => write!(f, "event {:#x}", addr),
+            BlockedOn::Notify(addr) => write!(f, "notify {:#x}", addr),
+            BlockedOn::Join(id) => write!(f, "join #{}", id),
+            BlockedOn::Sleep => write!(f, "sleep"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FiberState {
+    Ready,
+    Running,
+    Blocked,
+    Terminated,
+}
+
+impl std::fmt::Display for FiberState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FiberState::Ready => write!(f, "ready"),
+            FiberState::Running => write!(f, "running"),
+            FiberState::Blocked => write!(f, "blocked"),
+            FiberState::Terminated => write!(f, "terminated"),
+        }
+    }
+}
+
 #[repr(C)]
 pub struct Context {
     pub id: usize,
+    pub(crate) name: Option<String>,
     pub(crate) stack: Vec<u8>,
 
     pub(crate) generator: Option<Rc<crate::generator::Generator>>,
@@ -16,11 +82,27 @@ pub struct Context {
     pub(crate) wait_queue: std::collections::LinkedList<Ptr<Context>>,
     pub scheduler: Ptr<crate::scheduler::Scheduler>,
     pub terminated: bool,
-    fun: Box<dyn Fn()>,
+    fun: Option<Box<dyn FnOnce()>>,
     pub(crate) ready_hook: intrusive_collections::LinkedListLink,
     pub(crate) remote_hook: intrusive_collections::LinkedListLink,
     pub is_main: bool,
     pub is_dispatcher: bool,
+    pub(crate) cpu_time: Duration,
+    pub(crate) switches: usize,
+    pub(crate) resumed_at: Option<Instant>,
+    pub(crate) blocked_on: BlockedOn,
+    pub(crate) blocked_since: Option<Instant>,
+    pub(crate) sleep_deadline: Option<Instant>,
+    pub(crate) timed_out: bool,
+    /// The fiber panicked and is unwinding, set by the panic hook, see `Context::panicking`.
+    pub(crate) panicking: bool,
+    pub(crate) priority: i32,
+    /// Highest priority of fibers waiting on mutexes this context holds.
+    pub(crate) inherited_priority: Option<i32>,
+    /// Addresses of mutexes this context holds, see `BlockedOn::Mutex`.
+    pub(crate) owned_mutexes: Vec<usize>,
+    #[cfg(feature = "lockdep")]
+    pub(crate) held_locks: Vec<crate::detail::lockdep::LockClass>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -54,9 +136,10 @@ impl Context {
     pub fn new(stack: usize) -> Self {
         Self {
             id: 0,
+            name: None,
             stack: vec![0_u8; stack],
             generator: None,
-            fun: Box::new(move || {}),
+            fun: None,
             sp: std::ptr::null_mut(),
             bp: std::ptr::null_mut(),
             handle: crate::ptr::Ptr::null(),
@@ -68,6 +151,19 @@ impl Context {
             remote_hook: intrusive_collections::LinkedListLink::new(),
             is_main: false,
             is_dispatcher: false,
+            cpu_time: Duration::from_secs(0),
+            switches: 0,
+            resumed_at: None,
+            blocked_on: BlockedOn::Nothing,
+            blocked_since: None,
+            sleep_deadline: None,
+            timed_out: false,
+            panicking: false,
+            priority: 0,
+            inherited_priority: None,
+            owned_mutexes: vec![],
+            #[cfg(feature = "lockdep")]
+            held_locks: vec![],
         }
     }
 
@@ -87,15 +183,120 @@ impl Context {
         let prev = this;
     }*/
 
+    /// Claim the right to wake up this context.
+    ///
+    /// Waiting context stores address of the primitive it waits on in `twstatus`, the first waker swaps it with `-1`.
+    /// Returns `false` if the context was already woken up by somebody else, e.g. by its timer.
+    pub(crate) fn claim_wakeup(&self) -> bool {
+        let woken = -1i8 as *mut i8;
+        self.twstatus
+            .swap(woken, std::sync::atomic::Ordering::AcqRel)
+            != woken
+    }
+
     pub(crate) fn ready_is_linked(&self) -> bool {
         self.ready_hook.is_linked()
     }
 
-    pub(crate) fn apply<F: 'static, A: 'static + ApplyTo<F> + Clone>(&mut self, f: F, args: A) {
-        self.fun = Box::new(move || {
+    /// Total time this context spent running.
+    pub fn cpu_time(&self) -> Duration {
+        match self.resumed_at {
+            Some(start) => self.cpu_time + start.elapsed(),
+            None => self.cpu_time,
+        }
+    }
+
+    /// Number of times this context was switched to.
+    pub fn switch_count(&self) -> usize {
+        self.switches
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    /// Priority the scheduling algorithm should use: the priority set by `set_priority`, raised to the priority of
+    /// fibers waiting on mutexes this context holds.
+    pub fn priority(&self) -> i32 {
+        match self.inherited_priority {
+            Some(inherited) => inherited.max(self.priority),
+            None => self.priority,
+        }
+    }
+
+    /// Priority set by `set_priority`, 0 by default.
+    pub fn base_priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Set priority of `this` context, higher value means more important. Only priority-aware algorithms such as
+    /// `Priority` take it into account.
+    pub fn set_priority(this: Ptr<Context>, priority: i32) {
+        let old = this.priority();
+        this.get().priority = priority;
+        Context::priority_updated(this, old);
+        if let BlockedOn::Mutex(addr) = this.blocked_on {
+            crate::common::mutex::inherit_priority(addr, this.priority());
+        }
+    }
+
+    /// Let the scheduling algorithm know if priority of `this` is not `old` anymore.
+    pub(crate) fn priority_updated(this: Ptr<Context>, old: i32) {
+        if this.priority() != old && !this.scheduler.is_null() {
+            this.scheduler.get().algo.priority_changed(this);
+        }
+    }
+
+    pub fn state(&self) -> FiberState {
+        if self.terminated {
+            FiberState::Terminated
+        } else if !self.scheduler.is_null() && std::ptr::eq(self.scheduler.active_ctx.0, self) {
+            FiberState::Running
+        } else if self.ready_hook.is_linked() || self.remote_hook.is_linked() {
+            FiberState::Ready
+        } else {
+            FiberState::Blocked
+        }
+    }
+
+    pub fn blocked_on(&self) -> BlockedOn {
+        self.blocked_on
+    }
+
+    /// Record that this context is about to wait on `what`.
+    pub(crate) fn block_on(&mut self, what: BlockedOn) {
+        self.blocked_on = what;
+        self.blocked_since = Some(Instant::now());
+    }
+
+    pub(crate) fn unblock(&mut self) {
+        self.blocked_on = BlockedOn::Nothing;
+        self.blocked_since = None;
+    }
+
+    pub(crate) fn start_running(&mut self, now: Instant) {
+        self.resumed_at = Some(now);
+        self.switches += 1;
+    }
+
+    pub(crate) fn stop_running(&mut self, now: Instant) {
+        if let Some(start) = self.resumed_at.take() {
+            self.cpu_time += now - start;
+        }
+    }
+
+    pub(crate) fn apply<F: 'static, A: 'static + ApplyTo<F>>(&mut self, f: F, args: A) {
+        // Arguments are moved into the fiber, so handles like `Sender` are dropped once it finishes.
+        self.fun = Some(Box::new(move || {
+            // Panics are caught here, they can't unwind through the context switching code. `join` reports them.
             let result: Result<A::Result, Box<dyn std::any::Any + 'static + Send>> =
-                Ok(args.clone().apply_to(&f));
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| args.apply_to(&f)));
             let (generator, handle) = crate::scheduler::RUNTIME.with(|rt| {
+                rt.active_ctx.get().panicking = false;
                 (
                     rt.active_ctx.generator.clone(),
                     rt.active_ctx.handle.clone(),
@@ -104,26 +305,33 @@ impl Context {
             if generator.is_some() {
                 let gen = generator.as_ref().map(|x| x.clone()).unwrap();
 
-                gen.state
-                    .set(crate::generator::GeneratorState::Complete(Box::new(
-                        result.unwrap(),
-                    )));
+                // The panic is raised again by `Generator::resume` in the resuming fiber.
+                match result {
+                    Ok(value) => gen
+                        .state
+                        .set(crate::generator::GeneratorState::Complete(Box::new(value))),
+                    Err(payload) => gen.panic.set(Some(payload)),
+                }
                 crate::scheduler::RUNTIME.with(|rt| {
                     rt.get().resume(gen.to);
                 });
             } else if !handle.is_null() {
                 handle.get().value = Some(result.map(|x| Box::new(x) as Box<dyn std::any::Any>));
             }
-        })
+        }))
     }
 
     pub fn exec(&mut self) {
-        (self.fun)();
+        if let Some(fun) = self.fun.take() {
+            fun();
+        }
         while let Some(context) = self.wait_queue.pop_front() {
             self.scheduler.get().resume(context);
         }
         self.terminated = true;
         crate::scheduler::RUNTIME.with(|rt| {
+            SchedulerMetrics::incr(&rt.metrics.terminations);
+            crate::registry::unregister(Context::active());
             rt.get().terminated_queue.push_back(Context::active());
             rt.get().switch_without_current();
         })
@@ -133,6 +341,17 @@ impl Context {
         crate::scheduler::RUNTIME.with(|rt| rt.get().active_ctx)
     }
 
+    /// The active fiber is unwinding because of a panic.
+    ///
+    /// Unlike `std::thread::panicking`, this is false in other fibers that run while a fiber unwinds, e.g. when a
+    /// destructor of the unwinding fiber parks.
+    pub fn panicking() -> bool {
+        std::thread::panicking()
+            && crate::scheduler::RUNTIME
+                .try_with(|rt| rt.active_ctx.panicking)
+                .unwrap_or(true)
+    }
+
     pub fn get_stack(&self) -> &[u8] {
         &self.stack
     }
@@ -147,8 +366,64 @@ impl Context {
         }
 
         self.wait_queue.push_back(active_ctx);
+        active_ctx.get().block_on(BlockedOn::Join(self.id));
         active_ctx.scheduler.get().suspend();
+        active_ctx.get().unblock();
+    }
+}
+
+/// Remove `ctx` from wait queue, e.g. after its wait timed out.
+pub(crate) fn unlink_waiter(
+    queue: &mut std::collections::LinkedList<Ptr<Context>>,
+    ctx: Ptr<Context>,
+) {
+    let waiters = std::mem::take(queue);
+    *queue = waiters
+        .into_iter()
+        .filter(|waiter| *waiter != ctx)
+        .collect();
+}
+
+/// Wake up first context in `queue` that was not woken up by its timer yet, returns `false` if there was none.
+pub(crate) fn wake_one(queue: &mut std::collections::LinkedList<Ptr<Context>>) -> bool {
+    match claim_one(queue) {
+        Some(ctx) => {
+            Context::resume(ctx);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Pop first context in `queue` that was not woken up by its timer yet, the caller must resume it.
+pub(crate) fn claim_one(
+    queue: &mut std::collections::LinkedList<Ptr<Context>>,
+) -> Option<Ptr<Context>> {
+    while let Some(ctx) = queue.pop_front() {
+        if ctx.claim_wakeup() {
+            return Some(ctx);
+        }
     }
+    None
+}
+
+/// Chain a panic hook that marks the active fiber as unwinding, see `Context::panicking`.
+pub(crate) fn install_panic_hook() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if crate::scheduler::RUNTIME_STARTED.with(|started| started.get()) {
+                let _ = crate::scheduler::RUNTIME.try_with(|rt| {
+                    let active_ctx = rt.active_ctx;
+                    if !active_ctx.is_null() {
+                        active_ctx.get().panicking = true;
+                    }
+                });
+            }
+            previous(info);
+        }));
+    });
 }
 
 pub(crate) extern "C" fn ctx_function(context: *mut Context) {
@@ -225,6 +500,7 @@ impl_apply!(2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12);
 impl_apply!(2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13);
 impl_apply!(2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14);
 
+use crate::metrics::SchedulerMetrics;
 use crate::ptr::Ptr;
 use std::rc::Rc;
 
@@ -250,6 +526,11 @@ impl<T> ThreadHandle<T> {
         self.inner.thread
     }
 
+    /// Set priority of the fiber, see `Context::set_priority`.
+    pub fn set_priority(&self, priority: i32) {
+        Context::set_priority(self.inner.thread, priority);
+    }
+
     pub(crate) fn future_join(&self) -> T
     where
         T: 'static,
@@ -258,8 +539,10 @@ impl<T> ThreadHandle<T> {
             if let Some(value) = self.inner.0.read().value {
                 value.map(|value| *value.downcast().unwrap()).unwrap()
             } else {
-                if !self.inner.thread.ready_hook.is_linked() {
-                    self.inner.thread.scheduler.get().resume(self.inner.thread);
+                // Start the fiber if it was never scheduled, but don't wake it up if it is parked.
+                let thread = self.inner.thread;
+                if !thread.ready_hook.is_linked() && thread.blocked_on == BlockedOn::Nothing {
+                    thread.scheduler.get().resume(thread);
                 }
                 self.inner.thread.get().join();
                 self.inner
@@ -281,8 +564,10 @@ impl<T> ThreadHandle<T> {
             if let Some(value) = self.inner.0.read().value {
                 value.map(|value| *value.downcast().unwrap())
             } else {
-                if !self.inner.thread.ready_hook.is_linked() {
-                    self.inner.thread.scheduler.get().resume(self.inner.thread);
+                // Start the fiber if it was never scheduled, but don't wake it up if it is parked.
+                let thread = self.inner.thread;
+                if !thread.ready_hook.is_linked() && thread.blocked_on == BlockedOn::Nothing {
+                    thread.scheduler.get().resume(thread);
                 }
                 self.inner.thread.get().join();
                 self.inner
@@ -1,2 +1,4 @@
+#[cfg(feature = "lockdep")]
+pub mod lockdep;
 pub mod spinlock;
 pub mod spinlock_queue;
@@ -0,0 +1,90 @@
+//! Lock order checker for greenie mutexes.
+//!
+//! Every mutex belongs to a class identified by the source location where it was created. Each time a fiber acquires a
+//! mutex while holding others, edges from the held classes to the acquired one are added to a global lock order graph.
+//! If the new edge closes a cycle, the two conflicting orders are reported once, even if no deadlock happened.
+
+use crate::ctx::Context;
+use std::collections::{HashMap, HashSet};
+use std::panic::Location;
+
+pub type LockClass = &'static Location<'static>;
+
+#[derive(Default)]
+struct LockGraph {
+    edges: HashMap<LockClass, HashSet<LockClass>>,
+    reported: HashSet<(LockClass, LockClass)>,
+    reports: Vec<String>,
+}
+
+impl LockGraph {
+    /// Find path `from -> .. -> to` in lock order graph.
+    fn path(&self, from: LockClass, to: LockClass) -> Option<Vec<LockClass>> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![vec![from]];
+        while let Some(path) = stack.pop() {
+            let last = *path.last().unwrap();
+            if last == to {
+                return Some(path);
+            }
+            if !visited.insert(last) {
+                continue;
+            }
+            if let Some(next) = self.edges.get(&last) {
+                for class in next.iter() {
+                    let mut path = path.clone();
+                    path.push(*class);
+                    stack.push(path);
+                }
+            }
+        }
+        None
+    }
+}
+
+lazy_static::lazy_static!(
+    static ref GRAPH: parking_lot::Mutex<LockGraph> = parking_lot::Mutex::new(LockGraph::default());
+);
+
+/// Record that the active fiber acquired lock of `class` and check it against previously observed orders.
+pub(crate) fn acquired(class: LockClass) {
+    let active_ctx = Context::active();
+    let mut graph = GRAPH.lock();
+    for &held in active_ctx.held_locks.iter() {
+        if held == class || graph.edges.get(&held).is_some_and(|e| e.contains(&class)) {
+            continue;
+        }
+        if let Some(path) = graph.path(class, held) {
+            if graph.reported.insert((held, class)) {
+                let order = path
+                    .iter()
+                    .map(|class| class.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let report = format!(
+                    "greenie: lockdep: possible deadlock: fiber #{} acquires lock created at {} while holding lock created at {}, but the opposite order was observed before: {}",
+                    active_ctx.id, class, held, order
+                );
+                eprintln!("{}", report);
+                graph.reports.push(report);
+            }
+        }
+        graph.edges.entry(held).or_default().insert(class);
+    }
+    drop(graph);
+    active_ctx.get().held_locks.push(class);
+}
+
+/// Record that the active fiber released lock of `class`.
+pub(crate) fn released(class: LockClass) {
+    let active_ctx = Context::active();
+    let held = &mut active_ctx.get().held_locks;
+    if let Some(pos) = held.iter().rposition(|c| *c == class) {
+        held.remove(pos);
+    }
+}
+
+/// All lock order inversions reported so far.
+pub fn reports() -> Vec<String> {
+    GRAPH.lock().reports.clone()
+}
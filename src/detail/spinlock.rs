@@ -3,6 +3,11 @@ cfg_if::cfg_if! {
         pub type SpinLock = parking_lot::Mutex<()>;
         pub type SpinLockLock<'a> = parking_lot::MutexGuard<'a,()>;
         pub type SpinLockVal<T> = parking_lot::Mutex<T>;
+
+        /// `SpinLock::new` that can be used in constant context.
+        pub const fn const_spinlock() -> SpinLock {
+            parking_lot::const_mutex(())
+        }
     } else {
 
         pub type SpinLockVal<T> = T;
@@ -21,6 +26,10 @@ cfg_if::cfg_if! {
             }
 
         }
+        /// `SpinLock::new` that can be used in constant context.
+        pub const fn const_spinlock() -> SpinLock {
+            SpinLock
+        }
         #[derive(Copy,Clone,PartialEq,Eq)]
         pub struct SpinLockLock<'a> {
             _m: std::marker::PhantomData<&'a ()>
@@ -31,6 +31,10 @@ impl ContextSpinlockQueue {
         self.buffer.lock().is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.buffer.lock().iter().count()
+    }
+
     pub fn steal(&self) -> Ptr<Context> {
         let mut buffer = self.buffer.lock();
         let mut c = Ptr::null();
@@ -30,7 +30,7 @@ impl<T> Fiber<T> {
     ///     println!("{}",fiber.join().unwrap());
     /// });
     /// ```
-    pub fn new<F: FnOnce() -> T + Clone + 'static>(closure: F) -> Self {
+    pub fn new<F: FnOnce() -> T + 'static>(closure: F) -> Self {
         Self {
             handle: RUNTIME.with(|rt| {
                 rt.get()
@@ -59,7 +59,7 @@ impl<T> Fiber<T> {
     /// });
     /// ```
 
-    pub fn new_capture<F: 'static, A: 'static + ApplyTo<F, Result = T> + Clone>(
+    pub fn new_capture<F: 'static, A: 'static + ApplyTo<F, Result = T>>(
         closure: F,
         args: A,
     ) -> Self {
@@ -100,6 +100,32 @@ impl<T> Fiber<T> {
     pub fn is_alive(&self) -> bool {
         !self.handle.thread().terminated
     }
+    /// Unique identifier of this fiber.
+    pub fn id(&self) -> usize {
+        self.handle.thread_id()
+    }
+
+    pub fn name(&self) -> Option<String> {
+        self.get_thread().name.clone()
+    }
+
+    /// Set the name reported by `dump_fibers`.
+    pub fn set_name(&self, name: impl Into<String>) {
+        self.get_thread().get().set_name(name);
+    }
+    /// Set priority of the fiber, see `Context::set_priority`.
+    pub fn set_priority(&self, priority: i32) {
+        Context::set_priority(self.get_thread(), priority);
+    }
+    /// Total time this fiber spent running.
+    pub fn cpu_time(&self) -> std::time::Duration {
+        self.handle.thread().cpu_time()
+    }
+
+    /// Number of times this fiber was switched to.
+    pub fn switch_count(&self) -> usize {
+        self.handle.thread().switch_count()
+    }
     /// Pause fiber execution.
     pub fn suspend(&self) {
         self.get_thread()
@@ -26,6 +26,8 @@ use crate::scheduler::*;
 pub struct Generator {
     pub state: Ptr<GeneratorState>,
     pub(crate) complete: std::cell::Cell<bool>,
+    /// Payload of the panic the generator finished with, raised again by `resume`.
+    pub(crate) panic: std::cell::Cell<Option<Box<dyn std::any::Any + Send>>>,
     pub thread: Ptr<Context>,
     pub to: Ptr<Context>,
     pub is_join: bool,
@@ -35,10 +37,7 @@ use std::rc::Rc;
 
 impl Generator {
     /// Spawn generator
-    pub fn spawn<F: 'static, A: 'static + crate::ctx::ApplyTo<F> + Clone>(
-        closure: F,
-        args: A,
-    ) -> Rc<Self> {
+    pub fn spawn<F: 'static, A: 'static + crate::ctx::ApplyTo<F>>(closure: F, args: A) -> Rc<Self> {
         crate::scheduler::RUNTIME.with(|rt| {
             let to = rt.active_ctx;
             let thread = rt.get().spawn_not_schedule(closure, args).thread();
@@ -48,6 +47,7 @@ impl Generator {
                 thread,
                 to,
                 complete: std::cell::Cell::new(false),
+                panic: std::cell::Cell::new(None),
                 is_join: false,
             });
             thread.get().generator = Some(generator.clone());
@@ -59,6 +59,8 @@ impl Generator {
     /// This function will resume execution of the generator or start execution if it hasn't already. This call will return back into the
     /// generator's last suspension point, resuming execution from the latest yield. The generator will continue executing until it
     /// either yields or returns, at which point this function will return.
+    ///
+    /// If the generator panics, the panic is propagated to the caller.
     pub fn resume(&self) -> Result<GeneratorState, &'static str> {
         if self.complete.get() {
             return Err("Generator already complete");
@@ -67,6 +69,10 @@ impl Generator {
             rt.get().resume(self.thread);
             rt.get().switch_without_current();
         });
+        if let Some(payload) = self.panic.take() {
+            self.complete.set(true);
+            std::panic::resume_unwind(payload);
+        }
         if let GeneratorState::Complete(_) = &self.state.get() {
             self.complete.set(true);
         }
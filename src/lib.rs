@@ -75,9 +75,15 @@ pub mod ctx;
 pub mod detail;
 pub mod fiber;
 pub mod generator;
+pub mod metrics;
 pub mod ptr;
+pub mod registry;
 pub mod scheduler;
 pub use generator::generator_yield;
+pub use metrics::{runtime_metrics, RuntimeMetrics};
+#[cfg(unix)]
+pub use registry::dump_fibers_on_sigusr1;
+pub use registry::{dump_fibers, fibers, FiberInfo};
 pub use scheduler::{spawn_greenie, yield_thread};
 
 pub use greenie_proc::{greenify, greeny_main};
@@ -86,11 +92,8 @@ pub use greenie_proc::{greenify, greeny_main};
 /// The thread may sleep longer than the duration specified due to scheduling specifics or platform-dependent functionality. It will
 /// never sleep less.
 pub fn thread_sleep(duration: std::time::Duration) {
-    let now = std::time::Instant::now();
-
-    while duration > now.elapsed() {
-        crate::yield_thread();
-    }
+    let deadline = std::time::Instant::now() + duration;
+    scheduler::RUNTIME.with(|rt| rt.get().sleep_until(deadline));
 }
 
 pub use fiber::Fiber;
@@ -100,7 +103,9 @@ pub fn create_main(main_fn: fn()) {
     scheduler::RUNTIME.with(|rt| {
         let h = rt.get().spawn(|f, _| f(), (main_fn, ()));
 
-        h.join().unwrap();
+        if let Err(payload) = h.join() {
+            std::panic::resume_unwind(payload);
+        }
 
         //unsafe { std::intrinsics::drop_in_place(rt.0) };
     });
@@ -0,0 +1,109 @@
+//! Runtime counters collected by the scheduler.
+//!
+//! Every scheduler keeps a set of counters that are cheap to update on the hot path. A consistent copy
+//! of them can be taken at any time with `runtime_metrics` and exported in Prometheus text exposition format.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counters owned by a single `Scheduler`.
+///
+/// Counters are atomic because remote wakeups and steals are recorded by other OS threads when the `atomics` feature is enabled.
+#[derive(Default)]
+pub struct SchedulerMetrics {
+    pub(crate) context_switches: AtomicUsize,
+    pub(crate) spawns: AtomicUsize,
+    pub(crate) terminations: AtomicUsize,
+    pub(crate) remote_wakeups: AtomicUsize,
+    pub(crate) steals: AtomicUsize,
+}
+
+impl SchedulerMetrics {
+    pub(crate) fn incr(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of scheduler counters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeMetrics {
+    /// Number of switches from one context to another.
+    pub context_switches: usize,
+    /// Number of contexts created, including the dispatcher.
+    pub spawns: usize,
+    /// Number of contexts that finished execution.
+    pub terminations: usize,
+    /// Number of contexts waiting in the ready queue at the time of the snapshot, 0 if the scheduling algorithm does
+    /// not report it.
+    pub ready_queue_len: usize,
+    /// Number of contexts that were woken up by another OS thread.
+    pub remote_wakeups: usize,
+    /// Number of contexts stolen from this scheduler by other schedulers.
+    pub steals: usize,
+}
+
+impl RuntimeMetrics {
+    pub(crate) fn new(metrics: &SchedulerMetrics, ready_queue_len: usize) -> Self {
+        Self {
+            context_switches: metrics.context_switches.load(Ordering::Relaxed),
+            spawns: metrics.spawns.load(Ordering::Relaxed),
+            terminations: metrics.terminations.load(Ordering::Relaxed),
+            ready_queue_len,
+            remote_wakeups: metrics.remote_wakeups.load(Ordering::Relaxed),
+            steals: metrics.steals.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Render metrics in Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let metrics = [
+            (
+                "greenie_context_switches_total",
+                "counter",
+                "Number of context switches performed by the scheduler.",
+                self.context_switches,
+            ),
+            (
+                "greenie_spawns_total",
+                "counter",
+                "Number of contexts spawned.",
+                self.spawns,
+            ),
+            (
+                "greenie_terminations_total",
+                "counter",
+                "Number of contexts that finished execution.",
+                self.terminations,
+            ),
+            (
+                "greenie_ready_queue_length",
+                "gauge",
+                "Number of contexts waiting in the ready queue.",
+                self.ready_queue_len,
+            ),
+            (
+                "greenie_remote_wakeups_total",
+                "counter",
+                "Number of contexts woken up from another thread.",
+                self.remote_wakeups,
+            ),
+            (
+                "greenie_steals_total",
+                "counter",
+                "Number of contexts stolen by other schedulers.",
+                self.steals,
+            ),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics.iter() {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} {}\n", name, kind));
+            out.push_str(&format!("{} {}\n", name, value));
+        }
+        out
+    }
+}
+
+/// Take a snapshot of the counters of the scheduler running on the current thread.
+pub fn runtime_metrics() -> RuntimeMetrics {
+    crate::scheduler::RUNTIME.with(|rt| rt.metrics())
+}
@@ -0,0 +1,132 @@
+//! Global registry of live contexts.
+//!
+//! Every context gets a unique id when it is spawned and stays in the registry until it terminates. The registry
+//! is used for diagnostics only: reading another thread's contexts is racy and the returned information is a best-effort
+//! snapshot.
+
+use crate::ctx::*;
+use crate::ptr::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+lazy_static::lazy_static!(
+    static ref REGISTRY: parking_lot::Mutex<HashMap<usize, Ptr<Context>>> = parking_lot::Mutex::new(HashMap::new());
+);
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+static DUMP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn register(ctx: Ptr<Context>) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ctx.get().id = id;
+    REGISTRY.lock().insert(id, ctx);
+}
+
+pub(crate) fn unregister(ctx: Ptr<Context>) {
+    REGISTRY.lock().remove(&ctx.id);
+}
+
+/// Run `f` on every registered context.
+pub(crate) fn for_each(mut f: impl FnMut(Ptr<Context>)) {
+    let registry = REGISTRY.lock();
+    for ctx in registry.values() {
+        f(*ctx);
+    }
+}
+
+/// Information about a single live fiber.
+#[derive(Clone, Debug)]
+pub struct FiberInfo {
+    pub id: usize,
+    pub name: Option<String>,
+    pub state: FiberState,
+    pub blocked_on: BlockedOn,
+    /// How long the fiber has been waiting on `blocked_on`.
+    pub blocked_for: Option<Duration>,
+    /// Id of the fiber holding the mutex this fiber is blocked on.
+    pub held_by: Option<usize>,
+}
+
+impl FiberInfo {
+    pub(crate) fn new(ctx: &Context) -> Self {
+        Self {
+            id: ctx.id,
+            name: ctx.name.clone(),
+            state: ctx.state(),
+            blocked_on: ctx.blocked_on,
+            blocked_for: ctx.blocked_since.map(|since| since.elapsed()),
+            held_by: match ctx.blocked_on {
+                BlockedOn::Mutex(addr) => {
+                    let owner = crate::common::mutex::owner_of(addr);
+                    if owner.is_null() {
+                        None
+                    } else {
+                        Some(owner.id)
+                    }
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for FiberInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.id)?;
+        if let Some(name) = &self.name {
+            write!(f, " \"{}\"", name)?;
+        }
+        write!(f, " {}", self.state)?;
+        if self.blocked_on != BlockedOn::Nothing {
+            write!(f, " on {}", self.blocked_on)?;
+        }
+        if let Some(owner) = self.held_by {
+            write!(f, " held by #{}", owner)?;
+        }
+        if let Some(duration) = self.blocked_for {
+            write!(f, " for {:?}", duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// List every live fiber, ordered by id.
+pub fn fibers() -> Vec<FiberInfo> {
+    let mut fibers = vec![];
+    for_each(|ctx| fibers.push(FiberInfo::new(&ctx)));
+    fibers.sort_by_key(|info| info.id);
+    fibers
+}
+
+/// Print every live fiber to stderr.
+pub fn dump_fibers() {
+    let fibers = fibers();
+    let mut out = format!("greenie: {} live fibers\n", fibers.len());
+    for info in fibers.iter() {
+        out.push_str(&format!("  {}\n", info));
+    }
+    eprint!("{}", out);
+}
+
+/// Dump fibers to stderr whenever the process receives `SIGUSR1`.
+///
+/// The signal handler only sets a flag, the dump itself is done by the dispatcher the next time it runs.
+#[cfg(unix)]
+pub fn dump_fibers_on_sigusr1() {
+    extern "C" fn handler(_: libc::c_int) {
+        DUMP_REQUESTED.store(true, Ordering::Relaxed);
+    }
+    unsafe {
+        libc::signal(
+            libc::SIGUSR1,
+            handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+pub(crate) fn dump_if_requested() {
+    if DUMP_REQUESTED.swap(false, Ordering::Relaxed) {
+        dump_fibers();
+    }
+}
@@ -5,12 +5,14 @@ extern "C" {
 
 use crate::ctx::*;
 
+use crate::metrics::*;
 use crate::ptr::*;
 
 #[cfg(feature = "atomics")]
 intrusive_adapter!(pub SchedHook = Ptr<Scheduler> : Scheduler {scheduler_hook: intrusive_collections::LinkedListLink});
-#[cfg(feature = "atomics")]
 use crate::detail::spinlock::SpinLock;
+use std::collections::BTreeMap;
+use std::time::Instant;
 pub struct Scheduler {
     pub stack_size: usize,
     pub(crate) main_ctx: Ptr<Context>,
@@ -18,8 +20,13 @@ pub struct Scheduler {
     pub active_ctx: Ptr<Context>,
     pub current: usize,
     pub(crate) terminated_queue: std::collections::LinkedList<Ptr<Context>>,
+    /// Contexts waiting for a deadline, keyed by deadline and context id.
+    sleep_queue: BTreeMap<(Instant, usize), Ptr<Context>>,
+    sleep_queue_splk: SpinLock,
     pub(crate) algo: Box<dyn crate::algorithm::Algorithm>,
     pub shutdown: bool,
+    pub(crate) deadlock: Option<String>,
+    pub(crate) metrics: SchedulerMetrics,
     #[cfg(feature = "atomics")]
     scheduler_hook: intrusive_collections::LinkedListLink,
     #[cfg(feature = "atomics")]
@@ -40,9 +47,13 @@ impl Scheduler {
             current: 0,
             stack_size: 1024 * 1024 * 2,
             terminated_queue: std::collections::LinkedList::new(),
+            sleep_queue: BTreeMap::new(),
+            sleep_queue_splk: SpinLock::new(()),
             active_ctx: base_thread,
             algo: Box::new(crate::algorithm::round_robin::RoundRobin::new()),
             shutdown: false,
+            deadlock: None,
+            metrics: SchedulerMetrics::default(),
 
             #[cfg(feature = "atomics")]
             remote_queue: intrusive_collections::LinkedList::new(RemoteAdapter::new()),
@@ -70,13 +81,18 @@ impl Scheduler {
 
     #[cfg(feature = "atomics")]
     pub fn steal(&mut self) -> Ptr<Context> {
-        self.algo.steal()
+        let ctx = self.algo.steal();
+        if !ctx.is_null() {
+            SchedulerMetrics::incr(&self.metrics.steals);
+        }
+        ctx
     }
     #[cfg(feature = "atomics")]
     pub fn schedule_from_remote(&mut self, ctx: Ptr<Context>) {
         let lk = self.remote_queue_splk.lock();
         self.remote_queue.push_back(ctx);
         drop(lk);
+        SchedulerMetrics::incr(&self.metrics.remote_wakeups);
         self.algo.notify();
     }
     fn dispatch(&mut self) {
@@ -87,12 +103,32 @@ impl Scheduler {
             }
 
             self.cleanup();
+            crate::registry::dump_if_requested();
             #[cfg(feature = "atomics")]
             {
                 self.remote_ready2ready();
             }
+            self.sleep2ready();
 
             if !self.yield_() {
+                if let Some(deadline) = self.next_deadline() {
+                    // Nothing to run until the earliest timer expires.
+                    let now = Instant::now();
+                    if deadline > now {
+                        let wait = deadline - now;
+                        // Keep latency of remote wakeups low.
+                        #[cfg(feature = "atomics")]
+                        let wait = wait.min(std::time::Duration::from_millis(1));
+                        std::thread::sleep(wait);
+                    }
+                    continue;
+                }
+                if let Some(report) = self.detect_deadlock() {
+                    // Wake up main context so it can panic with the report.
+                    self.deadlock = Some(report);
+                    self.algo.awakened(self.main_ctx);
+                    continue;
+                }
                 break;
             }
         }
@@ -130,6 +166,7 @@ impl Scheduler {
             )
             .thread();
         self.dispatcher_ctx.get().is_dispatcher = true;
+        self.dispatcher_ctx.get().set_name("dispatcher");
         extern "C" {
             fn get_stackptr() -> *mut u8;
         }
@@ -150,6 +187,7 @@ impl Scheduler {
         }
         self.algo.awakened(self.active_ctx);
         self.active_ctx = next;
+        self.account_switch(prev, next);
 
         unsafe {
             switch_stack(&mut prev.get().sp, next.sp, next.get());
@@ -181,15 +219,67 @@ impl Scheduler {
             return false;
         }
         self.active_ctx = next;
+        self.account_switch(prev, next);
 
         unsafe {
             switch_stack(&mut prev.get().sp, next.sp, next.get());
         }
+        // Other contexts changed the scheduler while this one was switched out, don't read it through `self`: the
+        // compiler may keep fields it loaded before the switch.
+        let this = RUNTIME.with(|rt| *rt);
+        if this.active_ctx.is_main {
+            if let Some(report) = this.get().deadlock.take() {
+                panic!("{}", report);
+            }
+        }
 
         true
     }
 
-    pub fn spawn_not_schedule<F: 'static, A: 'static + ApplyTo<F> + Clone>(
+    /// Check whether there is nothing left to run while some contexts still wait on synchronization primitives.
+    ///
+    /// Returns report with the wait-for graph of this scheduler's contexts if deadlock found.
+    fn detect_deadlock(&self) -> Option<String> {
+        #[cfg(feature = "atomics")]
+        {
+            // Other schedulers may still wake up our contexts.
+            if crate::SCHEDULERS.lock().len() > 1 {
+                return None;
+            }
+        }
+        let mut blocked = vec![];
+        crate::registry::for_each(|ctx| {
+            if std::ptr::eq(ctx.scheduler.0, self)
+                && !ctx.terminated
+                && ctx.blocked_on != BlockedOn::Nothing
+            {
+                blocked.push(crate::registry::FiberInfo::new(&ctx));
+            }
+        });
+        if blocked.is_empty() {
+            return None;
+        }
+        blocked.sort_by_key(|info| info.id);
+        let mut report = String::from("greenie: deadlock detected, all fibers are blocked:\n");
+        for info in blocked.iter() {
+            report.push_str(&format!("  {}\n", info));
+        }
+        Some(report)
+    }
+
+    fn account_switch(&mut self, prev: Ptr<Context>, next: Ptr<Context>) {
+        let now = std::time::Instant::now();
+        prev.get().stop_running(now);
+        next.get().start_running(now);
+        SchedulerMetrics::incr(&self.metrics.context_switches);
+    }
+
+    /// Take a snapshot of this scheduler's counters.
+    pub fn metrics(&self) -> RuntimeMetrics {
+        RuntimeMetrics::new(&self.metrics, self.algo.ready_len())
+    }
+
+    pub fn spawn_not_schedule<F: 'static, A: 'static + ApplyTo<F>>(
         &mut self,
         f: F,
         args: A,
@@ -210,13 +300,15 @@ impl Scheduler {
                 init_stack(available.get().bp.offset(size as isize - 128), ctx_function);
         }
         available.get().scheduler = Ptr(self as *mut _);
+        SchedulerMetrics::incr(&self.metrics.spawns);
+        crate::registry::register(available);
         ThreadHandle {
             marker: std::marker::PhantomData,
             inner: inner_joinhandle,
         }
     }
 
-    pub fn spawn<F: 'static, A: 'static + ApplyTo<F> + Clone>(
+    pub fn spawn<F: 'static, A: 'static + ApplyTo<F>>(
         &mut self,
         f: F,
         args: A,
@@ -237,6 +329,8 @@ impl Scheduler {
                 init_stack(available.get().bp.offset(size as isize - 128), ctx_function);
         }
         available.get().scheduler = Ptr(self as *mut _);
+        SchedulerMetrics::incr(&self.metrics.spawns);
+        crate::registry::register(available);
         self.algo.awakened(available);
         ThreadHandle {
             marker: std::marker::PhantomData,
@@ -271,6 +365,7 @@ impl Scheduler {
     }
     #[cfg(feature = "atomics")]
     pub fn resume(&mut self, t: Ptr<Context>) {
+        t.scheduler.get().cancel_timer(t);
         if t.scheduler == Ptr(self as *mut Scheduler) {
             self.algo.awakened(t);
         } else {
@@ -280,6 +375,7 @@ impl Scheduler {
 
     #[cfg(not(feature = "atomics"))]
     pub fn resume(&mut self, t: Ptr<Context>) {
+        self.cancel_timer(t);
         self.algo.awakened(t);
     }
 
@@ -313,6 +409,92 @@ impl Scheduler {
         }
     }
 
+    /// Suspend active context waiting on `what` until somebody resumes it.
+    ///
+    /// Wait queue locks must be released before calling this: contexts resumed from other threads are moved to
+    /// the ready queue by this scheduler only after active context switched away.
+    pub(crate) fn park(&mut self, what: BlockedOn) {
+        let active_ctx = self.active_ctx;
+        active_ctx.get().block_on(what);
+        active_ctx.detach();
+        self.switch_without_current();
+        active_ctx.get().unblock();
+    }
+
+    /// Same as `park` but also wakes up active context once `deadline` passes.
+    ///
+    /// Returns `true` if the context was woken up by the timer. Whoever wakes up the context first must win
+    /// `Context::claim_wakeup`, so the waker and the timer never both resume it.
+    pub(crate) fn park_until(&mut self, what: BlockedOn, deadline: Instant) -> bool {
+        let active_ctx = self.active_ctx;
+        self.add_timer(active_ctx, deadline);
+        self.park(what);
+        active_ctx.timed_out
+    }
+
+    /// Put active context to sleep until `deadline`.
+    pub fn sleep_until(&mut self, deadline: Instant) {
+        self.active_ctx
+            .twstatus
+            .store(std::ptr::null_mut(), std::sync::atomic::Ordering::Release);
+        self.park_until(BlockedOn::Sleep, deadline);
+    }
+
+    fn add_timer(&mut self, ctx: Ptr<Context>, deadline: Instant) {
+        let _lk = self.sleep_queue_splk.lock();
+        ctx.get().timed_out = false;
+        ctx.get().sleep_deadline = Some(deadline);
+        self.sleep_queue.insert((deadline, ctx.id), ctx);
+    }
+
+    fn cancel_timer(&mut self, ctx: Ptr<Context>) {
+        let _lk = self.sleep_queue_splk.lock();
+        if let Some(deadline) = ctx.get().sleep_deadline.take() {
+            self.sleep_queue.remove(&(deadline, ctx.id));
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let _lk = self.sleep_queue_splk.lock();
+        self.sleep_queue
+            .keys()
+            .next()
+            .map(|(deadline, _)| *deadline)
+    }
+
+    /// Move contexts with expired deadlines to the ready queue.
+    fn sleep2ready(&mut self) {
+        let now = Instant::now();
+        loop {
+            let ctx = {
+                let _lk = self.sleep_queue_splk.lock();
+                let key = match self.sleep_queue.keys().next() {
+                    Some(key) if key.0 <= now => *key,
+                    _ => break,
+                };
+                let ctx = self.sleep_queue.remove(&key).unwrap();
+                ctx.get().sleep_deadline = None;
+                ctx
+            };
+            if ctx.claim_wakeup() {
+                ctx.get().timed_out = true;
+                self.algo.awakened(ctx);
+            }
+        }
+    }
+
+    /// Replace the scheduling algorithm, contexts that are ready to run are moved to `algo`.
+    pub fn set_algorithm(&mut self, mut algo: Box<dyn crate::algorithm::Algorithm>) {
+        std::mem::swap(&mut self.algo, &mut algo);
+        loop {
+            let ctx = algo.pick_next();
+            if ctx.is_null() {
+                break;
+            }
+            self.algo.awakened(ctx);
+        }
+    }
+
     /// Yield current thread
 
     pub fn yield_(&mut self) -> bool {
@@ -328,11 +510,15 @@ impl Scheduler {
 
 thread_local! {
     pub static RUNTIME: Ptr<Scheduler> = {
+        crate::ctx::install_panic_hook();
 
         let sched = Ptr::new(Scheduler::new());
         assert!(!sched.active_ctx.is_null());
         sched.get().active_ctx.get().scheduler = sched;
         sched.get().active_ctx.get().is_main = true;
+        sched.get().active_ctx.get().resumed_at = Some(std::time::Instant::now());
+        sched.get().active_ctx.get().set_name("main");
+        crate::registry::register(sched.active_ctx);
         sched.get().dispatcher_ctx = sched.get()
             .spawn(
                 || {
@@ -344,9 +530,13 @@ thread_local! {
             )
             .thread();
         sched.dispatcher_ctx.get().is_dispatcher = true;
+        sched.dispatcher_ctx.get().set_name("dispatcher");
+        RUNTIME_STARTED.with(|started| started.set(true));
         sched
     };
 
+    /// `RUNTIME` was created on this thread, the panic hook checks it so it never creates a scheduler itself.
+    pub(crate) static RUNTIME_STARTED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
 }
 /// Yields thread
 ///
@@ -369,7 +559,7 @@ pub extern "C" fn yield_thread() {
 ///    println!("{}", handle.join().unwrap());
 ///}
 /// ```
-pub fn spawn_greenie<F: 'static, A: 'static + ApplyTo<F> + Clone>(
+pub fn spawn_greenie<F: 'static, A: 'static + ApplyTo<F>>(
     f: F,
     args: A,
 ) -> ThreadHandle<A::Result> {
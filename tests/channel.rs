@@ -0,0 +1,254 @@
+use greenie::common::{channel, Channel, ChannelStatus, Receiver, Sender};
+use greenie::*;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn recv_timeout_on_empty_channel() {
+    create_main(|| {
+        let ch = Channel::<i32>::new(1);
+        let start = Instant::now();
+        assert_eq!(
+            ch.recv_timeout(Duration::from_millis(20)),
+            Err(ChannelStatus::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    });
+}
+
+#[test]
+fn send_timeout_on_full_channel() {
+    create_main(|| {
+        let ch = Channel::new(1);
+        assert_eq!(ch.send(1), ChannelStatus::Success);
+        assert_eq!(
+            ch.send_timeout(2, Duration::from_millis(10)),
+            ChannelStatus::Timeout
+        );
+        assert_eq!(ch.recv(), Ok(1));
+        assert_eq!(ch.try_recv(), Err(ChannelStatus::Empty));
+    });
+}
+
+#[test]
+fn timed_out_receiver_does_not_take_a_value() {
+    create_main(|| {
+        let ch = Channel::new(1);
+        let timed = spawn_greenie(
+            |ch: Rc<Channel<i32>>| ch.recv_timeout(Duration::from_millis(10)),
+            (ch.clone(),),
+        );
+        let untimed = spawn_greenie(|ch: Rc<Channel<i32>>| ch.recv(), (ch.clone(),));
+        assert_eq!(timed.join().unwrap(), Err(ChannelStatus::Timeout));
+        assert_eq!(ch.send(7), ChannelStatus::Success);
+        assert_eq!(untimed.join().unwrap(), Ok(7));
+    });
+}
+
+#[test]
+fn rendezvous_send_timeout_takes_value_back() {
+    create_main(|| {
+        let ch = Channel::new(0);
+        assert_eq!(
+            ch.send_timeout(1, Duration::from_millis(10)),
+            ChannelStatus::Timeout
+        );
+        assert_eq!(ch.try_recv(), Err(ChannelStatus::Empty));
+    });
+}
+
+#[test]
+fn unbounded_send_never_blocks() {
+    create_main(|| {
+        let ch = Channel::unbounded();
+        for i in 0..100 {
+            assert_eq!(ch.try_send(i), ChannelStatus::Success);
+            assert_eq!(ch.send(i), ChannelStatus::Success);
+        }
+        for i in 0..100 {
+            assert_eq!(ch.recv(), Ok(i));
+            assert_eq!(ch.try_recv(), Ok(i));
+        }
+        assert_eq!(ch.try_recv(), Err(ChannelStatus::Empty));
+    });
+}
+
+#[test]
+fn unbounded_recv_is_woken_by_a_send() {
+    create_main(|| {
+        let ch = Channel::unbounded();
+        let receiver = spawn_greenie(|ch: Rc<Channel<i32>>| ch.recv(), (ch.clone(),));
+        yield_thread();
+        assert_eq!(ch.send(5), ChannelStatus::Success);
+        assert_eq!(receiver.join().unwrap(), Ok(5));
+    });
+}
+
+#[test]
+fn closed_channel_rejects_sends_and_drains() {
+    create_main(|| {
+        let ch = Channel::unbounded();
+        ch.send(1);
+        ch.send(2);
+        ch.close();
+        assert!(ch.is_closed());
+        assert_eq!(ch.send(3), ChannelStatus::Closed);
+        assert_eq!(ch.try_send(3), ChannelStatus::Closed);
+        assert_eq!(ch.recv(), Ok(1));
+        assert_eq!(ch.try_recv(), Ok(2));
+        assert_eq!(ch.recv(), Err(ChannelStatus::Closed));
+        assert_eq!(ch.try_recv(), Err(ChannelStatus::Closed));
+    });
+}
+
+#[test]
+fn close_wakes_blocked_receivers() {
+    create_main(|| {
+        let ch = Channel::<i32>::unbounded();
+        let receivers: Vec<_> = (0..2)
+            .map(|_| spawn_greenie(|ch: Rc<Channel<i32>>| ch.recv(), (ch.clone(),)))
+            .collect();
+        yield_thread();
+        ch.close();
+        for receiver in receivers {
+            assert_eq!(receiver.join().unwrap(), Err(ChannelStatus::Closed));
+        }
+    });
+}
+
+#[test]
+fn send_fails_once_all_receivers_are_dropped() {
+    create_main(|| {
+        let (tx, rx) = channel::<i32>(1);
+        let rx2 = rx.clone();
+        drop(rx);
+        assert!(tx.send(1).is_ok());
+        drop(rx2);
+        assert!(tx.is_disconnected());
+        let err = tx.send(2).unwrap_err();
+        assert_eq!(err.status, ChannelStatus::Disconnected);
+        assert_eq!(err.value, 2);
+    });
+}
+
+#[test]
+fn recv_drains_then_reports_disconnect() {
+    create_main(|| {
+        let (tx, rx) = channel::<i32>(2);
+        tx.send(1).unwrap();
+        drop(tx);
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv(), Err(ChannelStatus::Disconnected));
+    });
+}
+
+#[test]
+fn dropping_last_sender_wakes_blocked_receiver() {
+    create_main(|| {
+        let (tx, rx) = channel::<i32>(1);
+        let receiver = spawn_greenie(|rx: Receiver<i32>| rx.recv(), (rx,));
+        yield_thread();
+        drop(tx);
+        assert_eq!(receiver.join().unwrap(), Err(ChannelStatus::Disconnected));
+    });
+}
+
+#[test]
+fn dropping_last_receiver_wakes_blocked_sender() {
+    create_main(|| {
+        let (tx, rx) = channel::<i32>(0);
+        let sender = spawn_greenie(
+            |tx: Sender<i32>| tx.send(3).map_err(|e| (e.status, e.value)),
+            (tx,),
+        );
+        yield_thread();
+        drop(rx);
+        assert_eq!(
+            sender.join().unwrap(),
+            Err((ChannelStatus::Disconnected, 3))
+        );
+    });
+}
+
+#[test]
+fn iter_drains_and_ends_on_close() {
+    create_main(|| {
+        let ch = Channel::new(3);
+        let producer = spawn_greenie(
+            |ch: Rc<Channel<i32>>| {
+                for i in 0..5 {
+                    ch.send(i);
+                }
+                ch.close();
+            },
+            (ch.clone(),),
+        );
+        assert_eq!(ch.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn try_iter_takes_only_buffered_values() {
+    create_main(|| {
+        let ch = Channel::new(3);
+        ch.send(1);
+        ch.send(2);
+        assert_eq!(ch.try_iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(ch.try_iter().next(), None);
+        assert!(!ch.is_closed());
+    });
+}
+
+#[test]
+fn receiver_into_iter_ends_when_senders_drop() {
+    create_main(|| {
+        let (tx, rx) = channel(1);
+        let producer = spawn_greenie(
+            |tx: Sender<i32>| {
+                for i in 0..3 {
+                    tx.send(i).unwrap();
+                }
+            },
+            (tx,),
+        );
+        assert_eq!(rx.into_iter().collect::<Vec<_>>(), [0, 1, 2]);
+        producer.join().unwrap();
+    });
+}
+
+#[test]
+fn bounded_channel_queries() {
+    create_main(|| {
+        let ch = Channel::new(2);
+        assert_eq!(ch.capacity(), Some(2));
+        assert!(ch.is_empty());
+        assert!(!ch.is_full());
+        ch.send(1);
+        assert_eq!(ch.len(), 1);
+        assert!(!ch.is_empty());
+        assert!(!ch.is_full());
+        ch.send(2);
+        assert_eq!(ch.len(), 2);
+        assert!(ch.is_full());
+        assert_eq!(Channel::<i32>::unbounded().capacity(), None);
+    });
+}
+
+#[test]
+fn rendezvous_channel_queries() {
+    create_main(|| {
+        let ch = Channel::new(0);
+        assert_eq!(ch.capacity(), Some(0));
+        assert!(ch.is_empty());
+        assert!(!ch.is_full());
+        let sender = spawn_greenie(|ch: Rc<Channel<i32>>| ch.send(1), (ch.clone(),));
+        yield_thread();
+        assert_eq!(ch.len(), 1);
+        assert!(ch.is_full());
+        assert_eq!(ch.recv(), Ok(1));
+        assert!(ch.is_empty());
+        assert!(!ch.is_full());
+        assert_eq!(sender.join().unwrap(), ChannelStatus::Success);
+    });
+}
@@ -0,0 +1,72 @@
+use greenie::common::{Condvar, CvStatus, Mutex};
+use greenie::*;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn wait_timeout_expires() {
+    create_main(|| {
+        let m = Mutex::new(());
+        let cv = Condvar::new();
+        let g = m.lock().unwrap();
+        let start = Instant::now();
+        assert_eq!(
+            cv.wait_timeout(&g, Duration::from_millis(20)),
+            CvStatus::Timeout
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    });
+}
+
+#[test]
+fn notify_before_timeout() {
+    create_main(|| {
+        let m = Mutex::new(false);
+        let cv = Rc::new(Condvar::new());
+        let h = spawn_greenie(
+            |m: Mutex<bool>, cv: Rc<Condvar>| {
+                let g = m.lock().unwrap();
+                cv.wait_timeout_pred(&g, Duration::from_secs(5), || *g)
+            },
+            (m.clone(), cv.clone()),
+        );
+        yield_thread();
+        *m.lock().unwrap() = true;
+        cv.notify_one();
+        assert_eq!(h.join().unwrap(), CvStatus::NoTimeout);
+    });
+}
+
+#[test]
+fn timed_out_waiter_does_not_swallow_notify() {
+    create_main(|| {
+        let m = Mutex::new(());
+        let cv = Rc::new(Condvar::new());
+        let timed = spawn_greenie(
+            |m: Mutex<()>, cv: Rc<Condvar>| {
+                let g = m.lock().unwrap();
+                cv.wait_timeout(&g, Duration::from_millis(10))
+            },
+            (m.clone(), cv.clone()),
+        );
+        let untimed = spawn_greenie(
+            |m: Mutex<()>, cv: Rc<Condvar>| {
+                let g = m.lock().unwrap();
+                cv.wait_for_mutex(&g);
+            },
+            (m.clone(), cv.clone()),
+        );
+        assert_eq!(timed.join().unwrap(), CvStatus::Timeout);
+        cv.notify_one();
+        untimed.join().unwrap();
+    });
+}
+
+#[test]
+fn sleep_lasts_at_least_the_duration() {
+    create_main(|| {
+        let start = Instant::now();
+        thread_sleep(Duration::from_millis(15));
+        assert!(start.elapsed() >= Duration::from_millis(15));
+    });
+}
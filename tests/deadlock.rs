@@ -0,0 +1,115 @@
+mod util;
+
+use greenie::common::{Mutex, PoisonError};
+use greenie::*;
+use std::time::Duration;
+use util::panic_message;
+
+#[test]
+#[should_panic(expected = "all fibers are blocked")]
+fn all_fibers_blocked() {
+    create_main(|| {
+        let m = Mutex::new(());
+        let _g = m.lock().unwrap();
+        let h = spawn_greenie(|m: Mutex<()>| drop(m.lock().unwrap()), (m.clone(),));
+        h.join().unwrap();
+    });
+}
+
+#[test]
+fn chain_of_waits_is_not_a_cycle() {
+    create_main(|| {
+        let m1 = Mutex::new(0);
+        let m2 = Mutex::new(0);
+        let g1 = m1.lock().unwrap();
+        let h1 = spawn_greenie(
+            |m1: Mutex<i32>, m2: Mutex<i32>| {
+                let mut g2 = m2.lock().unwrap();
+                *g2 += 1;
+                *m1.lock().unwrap() += 1;
+            },
+            (m1.clone(), m2.clone()),
+        );
+        let h2 = spawn_greenie(|m2: Mutex<i32>| *m2.lock().unwrap() += 1, (m2.clone(),));
+        yield_thread();
+        drop(g1);
+        h1.join().unwrap();
+        h2.join().unwrap();
+        assert_eq!((*m1.lock().unwrap(), *m2.lock().unwrap()), (1, 2));
+    });
+}
+
+#[test]
+fn relock_from_same_fiber() {
+    create_main(|| {
+        let h = spawn_greenie(
+            || {
+                let m = Mutex::new(());
+                let _g = m.lock().unwrap();
+                let _g2 = m.lock().unwrap();
+            },
+            (),
+        );
+        let message = panic_message(h.join().unwrap_err());
+        assert_eq!(message, "greenie: deadlock detected");
+    });
+}
+
+// Wait-for cycles are only checked in debug builds.
+#[cfg(debug_assertions)]
+#[test]
+fn lock_order_inversion() {
+    create_main(|| {
+        let m1 = Mutex::new(());
+        let m2 = Mutex::new(());
+        let h1 = spawn_greenie(
+            |m1: Mutex<()>, m2: Mutex<()>| {
+                let _g1 = m1.lock().unwrap_or_else(PoisonError::into_inner);
+                yield_thread();
+                let _g2 = m2.lock().unwrap_or_else(PoisonError::into_inner);
+            },
+            (m1.clone(), m2.clone()),
+        );
+        let h2 = spawn_greenie(
+            |m1: Mutex<()>, m2: Mutex<()>| {
+                let _g2 = m2.lock().unwrap_or_else(PoisonError::into_inner);
+                yield_thread();
+                let _g1 = m1.lock().unwrap_or_else(PoisonError::into_inner);
+            },
+            (m1.clone(), m2.clone()),
+        );
+        // The fiber that closes the cycle panics and releases its mutex, so the other one finishes. The mutex is
+        // poisoned by the panic.
+        let results = [h1.join(), h2.join()];
+        let message = match results {
+            [Ok(()), Err(e)] | [Err(e), Ok(())] => panic_message(e),
+            _ => panic!("exactly one fiber must detect the deadlock"),
+        };
+        assert!(
+            message.starts_with("greenie: deadlock detected: fiber #"),
+            "{}",
+            message
+        );
+    });
+}
+
+#[test]
+fn cycle_through_timed_wait_is_not_a_deadlock() {
+    create_main(|| {
+        let m1 = Mutex::new(());
+        let m2 = Mutex::new(());
+        let g1 = m1.lock().unwrap();
+        let h = spawn_greenie(
+            |m1: Mutex<()>, m2: Mutex<()>| {
+                let _g2 = m2.lock().unwrap();
+                m1.try_lock_for(Duration::from_millis(50)).is_ok()
+            },
+            (m1.clone(), m2.clone()),
+        );
+        yield_thread();
+        // Waits until the fiber above gives up on `m1` and releases `m2`.
+        drop(m2.lock().unwrap());
+        drop(g1);
+        assert!(!h.join().unwrap());
+    });
+}
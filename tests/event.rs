@@ -0,0 +1,62 @@
+use greenie::common::{Event, Notify};
+use greenie::*;
+use std::time::Duration;
+
+#[test]
+fn auto_reset_event_releases_one_waiter_per_set() {
+    create_main(|| {
+        let event = Event::auto_reset(false);
+        let waiters: Vec<_> = (0..2)
+            .map(|_| {
+                spawn_greenie(
+                    |e: Event| e.wait_timeout(Duration::from_millis(20)),
+                    (event,),
+                )
+            })
+            .collect();
+        yield_thread();
+        event.set();
+        let released: Vec<bool> = waiters.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(released, [true, false]);
+        assert!(!event.is_set());
+    });
+}
+
+#[test]
+fn timed_out_waiter_does_not_swallow_notify() {
+    create_main(|| {
+        let notify = Notify::new();
+        let timed = spawn_greenie(
+            |n: Notify| n.notified_timeout(Duration::from_millis(5)),
+            (notify,),
+        );
+        let untimed = spawn_greenie(|n: Notify| n.notified(), (notify,));
+        assert!(!timed.join().unwrap());
+        notify.notify_one();
+        untimed.join().unwrap();
+    });
+}
+
+#[test]
+fn joining_a_waiter_does_not_wake_it() {
+    create_main(|| {
+        let event = Event::manual_reset(false);
+        let waiter = spawn_greenie(
+            |e: Event| {
+                e.wait();
+                e.is_set()
+            },
+            (event,),
+        );
+        yield_thread();
+        let setter = spawn_greenie(
+            |e: Event| {
+                yield_thread();
+                e.set();
+            },
+            (event,),
+        );
+        assert!(waiter.join().unwrap());
+        setter.join().unwrap();
+    });
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "lockdep")]
+
+use greenie::common::Mutex;
+use greenie::detail::lockdep;
+use greenie::*;
+
+#[test]
+fn reports_inverted_lock_order() {
+    create_main(|| {
+        let a = Mutex::new(());
+        let b = Mutex::new(());
+        {
+            let _a = a.lock().unwrap();
+            let _b = b.lock().unwrap();
+        }
+        assert!(lockdep::reports().is_empty());
+        // Never deadlocks here, but could if another fiber took the locks in the first order.
+        let _b = b.lock().unwrap();
+        let _a = a.lock().unwrap();
+        let reports = lockdep::reports();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].starts_with("greenie: lockdep: possible deadlock"));
+        assert!(reports[0].contains("tests/lockdep.rs"));
+    });
+}
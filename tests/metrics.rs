@@ -0,0 +1,83 @@
+use greenie::ctx::Context;
+use greenie::*;
+use std::time::{Duration, Instant};
+
+#[test]
+fn counters_follow_spawns_and_switches() {
+    create_main(|| {
+        let before = runtime_metrics();
+        let fibers: Vec<_> = (0..3)
+            .map(|_| {
+                spawn_greenie(
+                    || {
+                        for _ in 0..3 {
+                            yield_thread();
+                        }
+                    },
+                    (),
+                )
+            })
+            .collect();
+        assert_eq!(runtime_metrics().spawns, before.spawns + 3);
+        for fiber in fibers {
+            fiber.join().unwrap();
+        }
+        let after = runtime_metrics();
+        assert_eq!(after.terminations, before.terminations + 3);
+        // Every fiber is switched to once to start and once after each yield.
+        assert!(after.context_switches >= before.context_switches + 3 * 4);
+    });
+}
+
+#[test]
+fn fiber_accounting() {
+    create_main(|| {
+        let busy = spawn_greenie(
+            || {
+                yield_thread();
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_millis(20) {}
+                yield_thread();
+                let ctx = Context::active();
+                (ctx.switch_count(), ctx.cpu_time())
+            },
+            (),
+        );
+        let sleepy = spawn_greenie(
+            || {
+                thread_sleep(Duration::from_millis(20));
+                Context::active().cpu_time()
+            },
+            (),
+        );
+        let (switches, busy_time) = busy.join().unwrap();
+        assert_eq!(switches, 3);
+        assert!(busy_time >= Duration::from_millis(20));
+        assert!(sleepy.join().unwrap() < Duration::from_millis(20));
+    });
+}
+
+#[test]
+fn prometheus_output_matches_snapshot() {
+    create_main(|| {
+        spawn_greenie(|| yield_thread(), ()).join().unwrap();
+        let metrics = runtime_metrics();
+        assert!(metrics.spawns > 0 && metrics.context_switches > 0);
+        let text = metrics.to_prometheus();
+        let lines: Vec<&str> = text.lines().collect();
+        for (name, value) in [
+            ("greenie_context_switches_total", metrics.context_switches),
+            ("greenie_spawns_total", metrics.spawns),
+            ("greenie_terminations_total", metrics.terminations),
+            ("greenie_ready_queue_length", metrics.ready_queue_len),
+        ] {
+            let sample = format!("{} {}", name, value);
+            let pos = lines
+                .iter()
+                .position(|line| *line == sample)
+                .expect(&sample);
+            assert!(lines[pos - 2].starts_with(&format!("# HELP {} ", name)));
+            assert!(lines[pos - 1].starts_with(&format!("# TYPE {} ", name)));
+        }
+    });
+}
@@ -0,0 +1,130 @@
+use greenie::common::{Fairness, Mutex};
+use greenie::*;
+use std::sync::TryLockError;
+use std::time::{Duration, Instant};
+
+#[test]
+fn try_lock_for_times_out_while_held() {
+    create_main(|| {
+        let m = Mutex::new(0);
+        let _guard = m.lock().unwrap();
+        let waiter = spawn_greenie(
+            |m: Mutex<i32>| {
+                let start = Instant::now();
+                let timed_out = matches!(
+                    m.try_lock_for(Duration::from_millis(20)),
+                    Err(TryLockError::WouldBlock)
+                );
+                (timed_out, start.elapsed())
+            },
+            (m.clone(),),
+        );
+        let (timed_out, elapsed) = waiter.join().unwrap();
+        assert!(timed_out);
+        assert!(elapsed >= Duration::from_millis(20));
+    });
+}
+
+#[test]
+fn try_lock_for_acquires_once_released() {
+    create_main(|| {
+        let m = Mutex::new(0);
+        let guard = m.lock().unwrap();
+        let waiter = spawn_greenie(
+            |m: Mutex<i32>| {
+                *m.try_lock_for(Duration::from_secs(5)).unwrap() += 1;
+            },
+            (m.clone(),),
+        );
+        yield_thread();
+        drop(guard);
+        waiter.join().unwrap();
+        assert_eq!(*m.lock().unwrap(), 1);
+    });
+}
+
+#[test]
+fn timed_out_waiter_is_skipped_by_handoff() {
+    create_main(|| {
+        let m = Mutex::with_fairness(0, Fairness::Fifo);
+        let guard = m.lock().unwrap();
+        let timed = spawn_greenie(
+            |m: Mutex<i32>| m.try_lock_for(Duration::from_millis(5)).is_ok(),
+            (m.clone(),),
+        );
+        let untimed = spawn_greenie(
+            |m: Mutex<i32>| {
+                *m.lock().unwrap() += 1;
+            },
+            (m.clone(),),
+        );
+        assert!(!timed.join().unwrap());
+        drop(guard);
+        untimed.join().unwrap();
+        assert_eq!(*m.lock().unwrap(), 1);
+    });
+}
+
+/// Three fibers lock the mutex three times each, holding it across a yield, and record the order they got it in.
+fn acquisition_order(fairness: Fairness) -> Vec<usize> {
+    let m = Mutex::with_fairness(vec![], fairness);
+    let fibers: Vec<_> = (0..3)
+        .map(|id| {
+            spawn_greenie(
+                |m: Mutex<Vec<usize>>, id: usize| {
+                    for _ in 0..3 {
+                        let mut order = m.lock().unwrap();
+                        order.push(id);
+                        yield_thread();
+                    }
+                },
+                (m.clone(), id),
+            )
+        })
+        .collect();
+    for fiber in fibers {
+        fiber.join().unwrap();
+    }
+    let order = m.lock().unwrap().clone();
+    order
+}
+
+#[test]
+fn fifo_mutex_hands_off_in_order() {
+    create_main(|| {
+        assert_eq!(
+            acquisition_order(Fairness::Fifo),
+            [0, 1, 2, 0, 1, 2, 0, 1, 2]
+        );
+    });
+}
+
+#[test]
+fn barging_mutex_lets_the_owner_relock() {
+    create_main(|| {
+        assert_eq!(
+            acquisition_order(Fairness::Barging),
+            [0, 0, 0, 1, 1, 1, 2, 2, 2]
+        );
+    });
+}
+
+#[test]
+fn eventual_mutex_hands_off_after_fair_timeout() {
+    create_main(|| {
+        let m = Mutex::with_fairness(vec![], Fairness::Eventual);
+        let mut guard = m.lock().unwrap();
+        let waiter = spawn_greenie(
+            |m: Mutex<Vec<usize>>| m.lock().unwrap().push(1),
+            (m.clone(),),
+        );
+        yield_thread();
+        guard.push(0);
+        // Hold the mutex longer than the 500us fair timeout, the next unlock must not let the owner barge.
+        std::thread::sleep(Duration::from_millis(1));
+        drop(guard);
+        m.lock().unwrap().push(0);
+        waiter.join().unwrap();
+        assert_eq!(*m.lock().unwrap(), [0, 1, 0]);
+    });
+}
@@ -0,0 +1,51 @@
+use greenie::common::{Lazy, Once};
+use greenie::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[test]
+fn callers_wait_for_a_yielding_initialization() {
+    create_main(|| {
+        let once = Rc::new(Once::new());
+        let runs = Rc::new(Cell::new(0));
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                spawn_greenie(
+                    |once: Rc<Once>, runs: Rc<Cell<i32>>| {
+                        once.call_once(|| {
+                            yield_thread();
+                            runs.set(runs.get() + 1);
+                        });
+                        // Every caller returns only after the initialization is done.
+                        runs.get()
+                    },
+                    (once.clone(), runs.clone()),
+                )
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.join().unwrap(), 1);
+        }
+        assert!(once.is_completed());
+    });
+}
+
+static INIT_RUNS: AtomicUsize = AtomicUsize::new(0);
+static SHARED: Lazy<usize> = Lazy::new(|| {
+    // Long enough for the other thread to get here while this one runs.
+    std::thread::sleep(Duration::from_millis(50));
+    INIT_RUNS.fetch_add(1, Ordering::SeqCst) + 1
+});
+
+#[test]
+fn lazy_static_is_initialized_once_across_threads() {
+    let threads: Vec<_> = (0..2)
+        .map(|_| std::thread::spawn(|| create_main(|| assert_eq!(*SHARED, 1))))
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(INIT_RUNS.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,23 @@
+use greenie::common::{oneshot, Canceled, OneshotReceiver, OneshotSender};
+use greenie::*;
+
+#[test]
+fn fiber_takes_a_handle_that_is_not_clone() {
+    create_main(|| {
+        let (tx, rx) = oneshot();
+        let receiver = spawn_greenie(|rx: OneshotReceiver<i32>| rx.recv(), (rx,));
+        yield_thread();
+        tx.send(7).unwrap();
+        assert_eq!(receiver.join().unwrap(), Ok(7));
+    });
+}
+
+#[test]
+fn handle_moved_into_a_fiber_is_dropped_when_it_finishes() {
+    create_main(|| {
+        let (tx, rx) = oneshot::<i32>();
+        let sender = spawn_greenie(|_tx: OneshotSender<i32>| (), (tx,));
+        sender.join().unwrap();
+        assert_eq!(rx.recv(), Err(Canceled));
+    });
+}
@@ -0,0 +1,83 @@
+use greenie::common::Mutex;
+use greenie::generator::GeneratorState;
+use greenie::*;
+
+mod util;
+use util::panic_message;
+
+#[test]
+fn panic_while_holding_the_lock_poisons_it() {
+    create_main(|| {
+        let m = Mutex::new(0);
+        let h = spawn_greenie(
+            |m: Mutex<i32>| {
+                let _guard = m.lock().unwrap();
+                panic!("boom");
+            },
+            (m.clone(),),
+        );
+        assert_eq!(panic_message(h.join().unwrap_err()), "boom");
+        assert!(m.is_poisoned());
+        assert!(m.lock().is_err());
+    });
+}
+
+/// Yields from its destructor, so other fibers run while the owning fiber unwinds.
+struct YieldOnDrop;
+
+impl Drop for YieldOnDrop {
+    fn drop(&mut self) {
+        yield_thread();
+    }
+}
+
+#[test]
+fn other_fibers_do_not_poison_while_one_unwinds() {
+    create_main(|| {
+        let m = Mutex::new(0);
+        let locker = spawn_greenie(
+            |m: Mutex<i32>| {
+                let mut guard = m.lock().unwrap();
+                // Let the other fiber panic, it yields back to us while unwinding.
+                yield_thread();
+                *guard += 1;
+            },
+            (m.clone(),),
+        );
+        let unwinding = spawn_greenie(
+            || {
+                let _yield = YieldOnDrop;
+                panic!("boom");
+            },
+            (),
+        );
+        locker.join().unwrap();
+        assert!(unwinding.join().is_err());
+        assert!(!m.is_poisoned());
+    });
+}
+
+#[test]
+fn generator_panic_propagates_to_resume() {
+    create_main(|| {
+        let generator = Generator::spawn(
+            |x: i32| {
+                generator_yield(x).unwrap();
+                panic!("generator failed");
+            },
+            (1,),
+        );
+        assert!(matches!(generator.resume(), Ok(GeneratorState::Yielded(_))));
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| generator.resume()))
+            .err()
+            .unwrap();
+        assert_eq!(panic_message(payload), "generator failed");
+        assert!(generator.resume().is_err());
+    });
+}
+
+#[test]
+#[should_panic(expected = "main failed")]
+fn main_fiber_panic_is_propagated() {
+    create_main(|| panic!("main failed"));
+}
@@ -0,0 +1,97 @@
+use greenie::algorithm::priority::Priority;
+use greenie::common::Mutex;
+use greenie::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+fn use_priority_scheduler() {
+    scheduler::RUNTIME.with(|rt| rt.get().set_algorithm(Box::new(Priority::new())));
+}
+
+/// Yields until `done` is set, gives up after a second. Returns whether `done` was set.
+fn spin_until(done: Rc<Cell<bool>>) -> bool {
+    let start = Instant::now();
+    while !done.get() && start.elapsed() < Duration::from_secs(1) {
+        yield_thread();
+    }
+    done.get()
+}
+
+#[test]
+fn timers_fire_while_high_priority_fibers_yield() {
+    create_main(|| {
+        use_priority_scheduler();
+        let done = Rc::new(Cell::new(false));
+        let sleeper = spawn_greenie(
+            |done: Rc<Cell<bool>>| {
+                thread_sleep(Duration::from_millis(10));
+                done.set(true);
+            },
+            (done.clone(),),
+        );
+        sleeper.set_priority(10);
+        // Two of them, so each yield switches to the other one.
+        let spinners: Vec<_> = (0..2)
+            .map(|_| {
+                let spinner = spawn_greenie(spin_until, (done.clone(),));
+                spinner.set_priority(10);
+                spinner
+            })
+            .collect();
+        sleeper.join().unwrap();
+        for spinner in spinners {
+            assert!(spinner.join().unwrap());
+        }
+    });
+}
+
+#[test]
+fn low_priority_owner_inherits_priority_of_waiter() {
+    create_main(|| {
+        use_priority_scheduler();
+        let m = Mutex::new(());
+        let log = Rc::new(RefCell::new(vec![]));
+        let low = spawn_greenie(
+            |m: Mutex<()>, log: Rc<RefCell<Vec<&'static str>>>| {
+                let _guard = m.lock().unwrap();
+                thread_sleep(Duration::from_millis(10));
+                log.borrow_mut().push("low");
+            },
+            (m.clone(), log.clone()),
+        );
+        low.set_priority(1);
+        // Let `low` take the mutex.
+        yield_thread();
+        let high = spawn_greenie(
+            |m: Mutex<()>, log: Rc<RefCell<Vec<&'static str>>>| {
+                let _guard = m.lock().unwrap();
+                log.borrow_mut().push("high");
+            },
+            (m.clone(), log.clone()),
+        );
+        high.set_priority(10);
+        let mediums: Vec<_> = (0..2)
+            .map(|_| {
+                let medium = spawn_greenie(
+                    |log: Rc<RefCell<Vec<&'static str>>>| {
+                        let start = Instant::now();
+                        while start.elapsed() < Duration::from_millis(200) {
+                            yield_thread();
+                        }
+                        log.borrow_mut().push("medium");
+                    },
+                    (log.clone(),),
+                );
+                medium.set_priority(5);
+                medium
+            })
+            .collect();
+        low.join().unwrap();
+        high.join().unwrap();
+        for medium in mediums {
+            medium.join().unwrap();
+        }
+        assert_eq!(*log.borrow(), ["low", "high", "medium", "medium"]);
+    });
+}
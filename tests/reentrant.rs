@@ -0,0 +1,27 @@
+use greenie::common::ReentrantMutex;
+use greenie::*;
+use std::cell::Cell;
+
+#[test]
+fn other_fiber_waits_for_the_outermost_guard() {
+    create_main(|| {
+        let m = ReentrantMutex::new(Cell::new(0));
+        let outer = m.lock();
+        let inner = m.lock();
+        let other = spawn_greenie(
+            |m: ReentrantMutex<Cell<i32>>| {
+                assert!(m.try_lock().is_none());
+                let guard = m.lock();
+                guard.get()
+            },
+            (m.clone(),),
+        );
+        yield_thread();
+        inner.set(1);
+        drop(inner);
+        yield_thread();
+        outer.set(2);
+        drop(outer);
+        assert_eq!(other.join().unwrap(), 2);
+    });
+}
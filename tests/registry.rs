@@ -0,0 +1,40 @@
+use greenie::common::Mutex;
+use greenie::ctx::{BlockedOn, Context, FiberState};
+use greenie::*;
+
+fn info(id: usize) -> Option<FiberInfo> {
+    // The registry is shared by all threads, other tests may have fibers in it too.
+    fibers().into_iter().find(|info| info.id == id)
+}
+
+#[test]
+fn fibers_reports_state_and_wait_target() {
+    create_main(|| {
+        let m = Mutex::new(());
+        let guard = m.lock().unwrap();
+        let blocked = spawn_greenie(|m: Mutex<()>| drop(m.lock().unwrap()), (m.clone(),));
+        yield_thread();
+        let fresh = spawn_greenie(|| {}, ());
+
+        let me = Context::active().id;
+        let main = info(me).unwrap();
+        assert_eq!(main.state, FiberState::Running);
+        assert_eq!(main.blocked_on, BlockedOn::Nothing);
+
+        let waiter = info(blocked.thread_id()).unwrap();
+        assert_eq!(waiter.state, FiberState::Blocked);
+        assert!(matches!(waiter.blocked_on, BlockedOn::Mutex(_)));
+        assert_eq!(waiter.held_by, Some(me));
+        assert!(waiter.blocked_for.is_some());
+        assert!(waiter.to_string().contains(&format!("held by #{}", me)));
+
+        assert_eq!(info(fresh.thread_id()).unwrap().state, FiberState::Ready);
+
+        drop(guard);
+        let (blocked_id, fresh_id) = (blocked.thread_id(), fresh.thread_id());
+        fresh.join().unwrap();
+        blocked.join().unwrap();
+        assert!(info(blocked_id).is_none());
+        assert!(info(fresh_id).is_none());
+    });
+}
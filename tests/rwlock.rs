@@ -0,0 +1,24 @@
+use greenie::common::RwLock;
+use greenie::*;
+
+#[test]
+fn writer_waits_for_readers() {
+    create_main(|| {
+        let lock = RwLock::new(0);
+        let r1 = lock.read().unwrap();
+        let r2 = lock.try_read().unwrap();
+        assert!(lock.try_write().is_err());
+        let h = spawn_greenie(
+            |lock: RwLock<i32>| *lock.write().unwrap() += 1,
+            (lock.clone(),),
+        );
+        yield_thread();
+        // A writer is waiting, new readers queue up behind it.
+        assert!(lock.try_read().is_err());
+        assert_eq!(*r1 + *r2, 0);
+        drop(r1);
+        drop(r2);
+        h.join().unwrap();
+        assert_eq!(*lock.read().unwrap(), 1);
+    });
+}
@@ -0,0 +1,152 @@
+use greenie::common::{Channel, ChannelStatus};
+use greenie::*;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn first_ready_recv_arm_is_selected() {
+    create_main(|| {
+        let numbers = Channel::<i32>::new(2);
+        let words = Channel::<&'static str>::new(2);
+        words.send("hello");
+        let result = greenie::select! {
+            recv(numbers) -> n => n.unwrap().to_string(),
+            recv(words) -> w => w.unwrap().to_string(),
+        };
+        assert_eq!(result, "hello");
+        assert_eq!(words.try_recv(), Err(ChannelStatus::Empty));
+    });
+}
+
+#[test]
+fn default_arm_runs_when_nothing_is_ready() {
+    create_main(|| {
+        let ch = Channel::<i32>::new(1);
+        let result = greenie::select! {
+            recv(ch) -> _ => "recv",
+            default => "default",
+        };
+        assert_eq!(result, "default");
+    });
+}
+
+#[test]
+fn timeout_arm_runs_after_the_duration() {
+    create_main(|| {
+        let ch = Channel::<i32>::new(1);
+        let start = Instant::now();
+        let result = greenie::select! {
+            recv(ch) -> _ => "recv",
+            timeout(Duration::from_millis(20)) => "timeout",
+        };
+        assert_eq!(result, "timeout");
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    });
+}
+
+#[test]
+fn blocked_select_is_woken_by_a_send() {
+    create_main(|| {
+        let a = Channel::<i32>::new(1);
+        let b = Channel::<i32>::new(1);
+        let sender = spawn_greenie(
+            |b: Rc<Channel<i32>>| {
+                yield_thread();
+                b.send(42)
+            },
+            (b.clone(),),
+        );
+        let result = greenie::select! {
+            recv(a) -> _ => None,
+            recv(b) -> v => Some(v),
+        };
+        assert_eq!(result, Some(Ok(42)));
+        assert_eq!(sender.join().unwrap(), ChannelStatus::Success);
+    });
+}
+
+#[test]
+fn send_arm_sends_only_when_selected() {
+    create_main(|| {
+        let full = Channel::new(1);
+        let open = Channel::new(1);
+        full.send(0);
+        let result = greenie::select! {
+            send(full, 1) -> s => ("full", s),
+            send(open, 2) -> s => ("open", s),
+        };
+        assert_eq!(result, ("open", ChannelStatus::Success));
+        assert_eq!(full.try_recv(), Ok(0));
+        assert_eq!(full.try_recv(), Err(ChannelStatus::Empty));
+        assert_eq!(open.try_recv(), Ok(2));
+    });
+}
+
+#[test]
+fn rendezvous_send_arm_is_woken_by_a_receiver() {
+    create_main(|| {
+        let ch = Channel::<i32>::new(0);
+        let receiver = spawn_greenie(
+            |ch: Rc<Channel<i32>>| {
+                yield_thread();
+                ch.recv()
+            },
+            (ch.clone(),),
+        );
+        let start = Instant::now();
+        let result = greenie::select! {
+            send(ch, 5) -> s => Some(s),
+            timeout(Duration::from_secs(3)) => None,
+        };
+        assert_eq!(result, Some(ChannelStatus::Success));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(receiver.join().unwrap(), Ok(5));
+    });
+}
+
+#[test]
+fn rendezvous_send_arm_on_split_channel() {
+    create_main(|| {
+        let (tx, rx) = greenie::common::channel::<i32>(0);
+        let receiver = spawn_greenie(
+            |rx: greenie::common::Receiver<i32>| {
+                yield_thread();
+                rx.recv()
+            },
+            (rx,),
+        );
+        let start = Instant::now();
+        let result = greenie::select! {
+            send(tx, 5) -> s => s.is_ok(),
+            timeout(Duration::from_secs(3)) => false,
+        };
+        assert!(result);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(receiver.join().unwrap(), Ok(5));
+    });
+}
+
+#[test]
+fn rendezvous_try_send_ignores_a_selecting_receiver() {
+    create_main(|| {
+        let ch = Channel::<i32>::new(0);
+        let selector = spawn_greenie(
+            |ch: Rc<Channel<i32>>| {
+                greenie::select! {
+                    recv(ch) -> v => v.ok(),
+                    timeout(Duration::from_millis(20)) => None,
+                }
+            },
+            (ch.clone(),),
+        );
+        yield_thread();
+        assert_eq!(ch.try_send(1), ChannelStatus::Full);
+        assert_eq!(ch.try_recv(), Err(ChannelStatus::Empty));
+        assert_eq!(selector.join().unwrap(), None);
+
+        let receiver = spawn_greenie(|ch: Rc<Channel<i32>>| ch.recv(), (ch.clone(),));
+        yield_thread();
+        assert_eq!(ch.try_send(2), ChannelStatus::Success);
+        assert_eq!(receiver.join().unwrap(), Ok(2));
+    });
+}
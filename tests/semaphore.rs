@@ -0,0 +1,21 @@
+use greenie::common::Semaphore;
+use greenie::*;
+
+#[test]
+fn waiters_are_served_in_fifo_order() {
+    create_main(|| {
+        let sem = Semaphore::new(1);
+        let permit = sem.acquire(1);
+        // The large request at the head of the queue is not overtaken by the small one.
+        let big = spawn_greenie(|sem: Semaphore| sem.acquire(2).num_permits(), (sem,));
+        yield_thread();
+        let small = spawn_greenie(|sem: Semaphore| sem.acquire(1).num_permits(), (sem,));
+        yield_thread();
+        sem.release(1);
+        assert!(sem.try_acquire(1).is_none());
+        drop(permit);
+        assert_eq!(big.join().unwrap(), 2);
+        assert_eq!(small.join().unwrap(), 1);
+        assert_eq!(sem.available_permits(), 2);
+    });
+}
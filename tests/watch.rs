@@ -0,0 +1,50 @@
+use greenie::common::watch;
+use greenie::*;
+
+#[test]
+fn refs_from_sender_and_receiver_coexist() {
+    create_main(|| {
+        let (tx, rx) = watch::channel(1);
+        let a = rx.borrow();
+        let b = tx.borrow();
+        assert_eq!(*a + *b, 2);
+    });
+}
+
+#[test]
+fn changed_while_holding_a_ref() {
+    create_main(|| {
+        let (tx, mut rx) = watch::channel(0);
+        tx.send(1).unwrap();
+        let rx2 = rx.clone();
+        let value = rx2.borrow();
+        rx.changed().unwrap();
+        assert_eq!(*value, 1);
+        drop(value);
+        drop(tx);
+        assert_eq!(
+            rx.changed(),
+            Err(greenie::common::ChannelStatus::Disconnected)
+        );
+    });
+}
+
+#[test]
+fn send_waits_for_refs_held_by_other_fibers() {
+    create_main(|| {
+        let (tx, rx) = watch::channel(0);
+        let reader = spawn_greenie(
+            |rx: watch::Receiver<i32>| {
+                let value = rx.borrow();
+                yield_thread();
+                yield_thread();
+                *value
+            },
+            (rx.clone(),),
+        );
+        yield_thread();
+        tx.send(1).unwrap();
+        assert_eq!(reader.join().unwrap(), 0);
+        assert_eq!(*rx.borrow(), 1);
+    });
+}